@@ -0,0 +1,42 @@
+//! Checks that the number and expression types build without `std`. Build it
+//! on its own with `cargo build -p countdown_no_std`, since building the whole
+//! workspace turns `std` back on for `rust_countdown`.
+#![no_std]
+extern crate alloc;
+
+use alloc::boxed::Box;
+
+use rust_countdown::base_types::{
+    expressions::{Expression, Operator},
+    numbers::{ModularNumberSystem, NumberSystem},
+    rational::{Rational, RationalNumberSystem},
+};
+
+/// Builds `(a + b) * c` modulo `modulus`, returning `None` if any step fails.
+pub fn modular_sum_product(
+    modulus: u32,
+    a: u32,
+    b: u32,
+    c: u32,
+) -> Option<Expression<u32>> {
+    let system = ModularNumberSystem::new(modulus);
+    let sum = Operator::Add.apply(&system, a, b)?;
+    let product = system.mul(sum, c)?;
+    let expr = Expression::Application(
+        product,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            sum,
+            Operator::Add,
+            Box::new(Expression::Value(a)),
+            Box::new(Expression::Value(b)),
+        )),
+        Box::new(Expression::Value(c)),
+    );
+    expr.check(&system).then_some(expr)
+}
+
+/// Divides two whole numbers exactly.
+pub fn exact_quotient(one: i64, other: i64) -> Option<Rational<i64>> {
+    RationalNumberSystem.div(Rational::from(one), Rational::from(other))
+}
@@ -1,16 +1,28 @@
 //! Expressions are the base of the countdown problem.
-use std::{
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
     fmt::{Debug, Display},
-    mem::transmute,
+    ops::{BitAnd, BitOr, Not},
 };
 
 #[cfg(feature = "parsing")]
 use crate::parsing::{Parsable, Token};
 
+#[cfg(feature = "parsing")]
+use super::numbers::NormalNumberSystem;
 use super::numbers::{NumberSystem, NumberType};
 
-///  Operator type represents an arithmetic binary operation.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///  Operator type represents an arithmetic binary operation. Operators are
+/// ordered by their bit, so in the order they are declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Operator {
     /// This is addition `(+)`
@@ -21,12 +33,18 @@ pub enum Operator {
     Mul = 4,
     /// This is division `(/)`
     Div = 8,
+    /// This is exponentiation `(^)`
+    Pow = 16,
+    /// This is digit concatenation `(||)`
+    Concat = 32,
+    /// This is the remainder `(%)`
+    Mod = 64,
 }
 
 #[cfg(feature = "parsing")]
 impl Parsable for Operator {
     fn parse(
-        tokens: &mut std::collections::VecDeque<Token>,
+        tokens: &mut alloc::collections::VecDeque<Token>,
     ) -> Result<Self, String> {
         match tokens.pop_front().ok_or_else(|| {
             String::from("Parse Error: Operator found nothing")
@@ -37,6 +55,16 @@ impl Parsable for Operator {
                     | '-' => Ok(Operator::Sub),
                     | '*' => Ok(Operator::Mul),
                     | '/' => Ok(Operator::Div),
+                    | '^' => Ok(Operator::Pow),
+                    | '%' => Ok(Operator::Mod),
+                    | '|' if matches!(
+                        tokens.front(),
+                        Some(Token::Punctuation('|'))
+                    ) =>
+                    {
+                        tokens.pop_front();
+                        Ok(Operator::Concat)
+                    }
                     | a => Err(format!("Expected an operator found {a:?}")),
                 }
             }
@@ -46,6 +74,9 @@ impl Parsable for Operator {
                     | "sub" => Ok(Operator::Sub),
                     | "mul" => Ok(Operator::Mul),
                     | "div" => Ok(Operator::Div),
+                    | "pow" => Ok(Operator::Pow),
+                    | "concat" => Ok(Operator::Concat),
+                    | "mod" => Ok(Operator::Mod),
                     | a => Err(format!("Expected an operator found {a:?}")),
                 }
             }
@@ -69,28 +100,103 @@ impl Operator {
             | Operator::Sub => system.sub(one, other),
             | Operator::Mul => system.mul(one, other),
             | Operator::Div => system.div(one, other),
+            | Operator::Pow => system.pow(one, other),
+            | Operator::Concat => system.concat(one, other),
+            | Operator::Mod => system.rem(one, other),
+        }
+    }
+    /// How tightly the operator binds, with sums lowest and powers and
+    /// concatenation highest.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            | Operator::Add | Operator::Sub => 1,
+            | Operator::Mul | Operator::Div | Operator::Mod => 2,
+            | Operator::Pow | Operator::Concat => 3,
+        }
+    }
+    /// Whether an operand which applies `child` needs parentheses when
+    /// written as the left or right operand of this operator.
+    fn needs_parentheses(&self, child: Operator, is_right: bool) -> bool {
+        let associative = child == *self && self.is_associative();
+        child.precedence() < self.precedence()
+            || (is_right
+                && child.precedence() == self.precedence()
+                && !associative)
+    }
+    /// Whether `a op b == b op a` for all operands
+    pub fn is_commutative(&self) -> bool {
+        matches!(self, Operator::Add | Operator::Mul)
+    }
+    /// Whether `(a op b) op c == a op (b op c)` for all operands. Digit
+    /// concatenation is not, since `2 || (0 || 5)` drops the zero.
+    pub fn is_associative(&self) -> bool {
+        matches!(self, Operator::Add | Operator::Mul)
+    }
+    /// Whether `a self (b right c)` has the same value as `(a self b) self c`,
+    /// the chain nested to the left over the same numbers in the same order.
+    /// This holds for chains of an associative operator, and for
+    /// `a - (b + c) == (a - b) - c` and `a / (b * c) == (a / b) / c`. The
+    /// left-nested form may still be undefined or dropped, such as when
+    /// `a - b` is zero, so it only replaces the right-nested one when a
+    /// number system builds it, see [`Expression::is_valid_application`].
+    pub fn nests_redundantly(&self, right: Operator) -> bool {
+        match (*self, right) {
+            | (Operator::Sub, Operator::Add) => true,
+            | (Operator::Div, Operator::Mul) => true,
+            | (operator, right) => {
+                operator.is_associative() && operator == right
+            }
+        }
+    }
+    /// The position of the operator's bit, which indexes histograms such as
+    /// [`operator_histogram`].
+    pub fn index(&self) -> usize {
+        (*self as u8).trailing_zeros() as usize
+    }
+    /// Gets the operator whose bit is `bit`, or `None` if it is not exactly
+    /// the bit of one operator.
+    pub fn from_u8(bit: u8) -> Option<Operator> {
+        match bit {
+            | 1 => Some(Operator::Add),
+            | 2 => Some(Operator::Sub),
+            | 4 => Some(Operator::Mul),
+            | 8 => Some(Operator::Div),
+            | 16 => Some(Operator::Pow),
+            | 32 => Some(Operator::Concat),
+            | 64 => Some(Operator::Mod),
+            | _ => None,
         }
     }
 }
 
 impl Display for Operator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_str(match self {
             | Operator::Add => "+",
             | Operator::Sub => "-",
             | Operator::Mul => "*",
             | Operator::Div => "/",
+            | Operator::Pow => "^",
+            | Operator::Concat => "||",
+            | Operator::Mod => "%",
         })
     }
 }
 impl AsRef<Operators> for Operator {
     fn as_ref(&self) -> &Operators {
-        // Safety: Operator has the same size and representation as Operators
-        unsafe { transmute(self) }
+        match self {
+            | Operator::Add => &Operators(Operator::Add as u8),
+            | Operator::Sub => &Operators(Operator::Sub as u8),
+            | Operator::Mul => &Operators(Operator::Mul as u8),
+            | Operator::Div => &Operators(Operator::Div as u8),
+            | Operator::Pow => &Operators(Operator::Pow as u8),
+            | Operator::Concat => &Operators(Operator::Concat as u8),
+            | Operator::Mod => &Operators(Operator::Mod as u8),
+        }
     }
 }
 /// represents a collection of operators.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Operators(u8);
 /// an iterator through different operators
 pub struct OperatorIterator {
@@ -98,9 +204,66 @@ pub struct OperatorIterator {
     value: u8,
 }
 impl Operators {
-    /// A value representing all the operators
+    /// A value representing all the basic operators, `+-*/`
     pub const ALL: Operators = Operators(0xF);
+    /// A value representing every operator, including powers,
+    /// concatenation and remainders
+    pub const EVERY: Operators = Operators(0x7F);
+    /// A value representing none of the operators
+    pub const NONE: Operators = Operators(0);
+    /// Whether the operator is in the collection
+    pub fn contains(&self, op: Operator) -> bool {
+        self.0 & op as u8 != 0
+    }
+    /// The number of operators in the collection
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+    /// Whether the collection has no operators
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// Adds the operator to the collection
+    pub fn insert(&mut self, op: Operator) {
+        self.0 |= op as u8;
+    }
+    /// Removes the operator from the collection
+    pub fn remove(&mut self, op: Operator) {
+        self.0 &= !(op as u8);
+    }
+    /// Collects the operators written as symbols like `+-*/`, where
+    /// concatenation is the pair `||`. Unlike parsing this needs no
+    /// tokenizer, and whitespace is not allowed.
+    pub fn from_chars(s: &str) -> Result<Operators, String> {
+        let mut operators = Operators(0);
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            operators.insert(match c {
+                | '+' => Operator::Add,
+                | '-' => Operator::Sub,
+                | '*' => Operator::Mul,
+                | '/' => Operator::Div,
+                | '^' => Operator::Pow,
+                | '%' => Operator::Mod,
+                | '|' if chars.next() == Some('|') => Operator::Concat,
+                | c => return Err(format!("Unknown operator {c:?} in {s:?}")),
+            });
+        }
+        Ok(operators)
+    }
+    /// Iterates through every non-empty subset of these operators, from the
+    /// largest bit pattern down.
+    pub fn subsets(self) -> impl Iterator<Item = Operators> {
+        let mask = self.0;
+        core::iter::successors((mask != 0).then_some(mask), move |&sub| {
+            let next = (sub - 1) & mask;
+            (next != 0).then_some(next)
+        })
+        .map(Operators)
+    }
 }
+/// The number of different operators
+pub const OPERATOR_COUNT: usize = Operators::EVERY.0.count_ones() as usize;
 impl IntoIterator for Operators {
     type Item = Operator;
 
@@ -123,15 +286,12 @@ impl Iterator for OperatorIterator {
         }
         loop {
             let mask = self.current_bit;
-            if mask & 0xF == 0 {
+            if mask & Operators::EVERY.0 == 0 {
                 return None;
             }
             self.current_bit <<= 1;
             if (self.value & mask) != 0 {
-                // Safety: We know that it will be safe due to the definition
-                return Some(unsafe {
-                    transmute::<u8, Operator>(self.value & mask)
-                });
+                return Operator::from_u8(mask);
             }
         }
     }
@@ -142,20 +302,144 @@ impl FromIterator<Operator> for Operators {
         Operators(iter.into_iter().map(|a| a as u8).fold(0, |a, b| a | b))
     }
 }
+/// The union of every set of operators, such as several rule presets.
+impl FromIterator<Operators> for Operators {
+    fn from_iter<T: IntoIterator<Item = Operators>>(iter: T) -> Self {
+        iter.into_iter().fold(Operators::NONE, BitOr::bitor)
+    }
+}
+/// The operators in either set
+impl BitOr for Operators {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Operators(self.0 | rhs.0)
+    }
+}
+/// The operators in both sets
+impl BitAnd for Operators {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Operators(self.0 & rhs.0)
+    }
+}
+/// Every operator which is not in the set
+impl Not for Operators {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Operators(!self.0 & Operators::EVERY.0)
+    }
+}
+/// Writes the contained operators as a compact run of symbols such as `+-*/`,
+/// which [`Operators::parse`] reads back.
+impl Display for Operators {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.into_iter().try_for_each(|op| Display::fmt(&op, f))
+    }
+}
+/// Parses a compact run of operator symbols such as `+-*/`, stopping at the
+/// first token which is not punctuation.
+#[cfg(feature = "parsing")]
+impl Parsable for Operators {
+    fn parse(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+    ) -> Result<Self, String> {
+        let mut operators = Vec::new();
+        while let Some(Token::Punctuation(_)) = tokens.front() {
+            operators.push(Operator::parse(tokens)?);
+        }
+        if operators.is_empty() {
+            return Err(format!(
+                "Expected an operator found {:?}",
+                tokens.front()
+            ));
+        }
+        Ok(Operators::from_iter(operators))
+    }
+}
 
 /// A type representing an expression which can either be a value or an
-/// application of an operator over two expressions.
+/// application of an operator over two expressions. Equality and hashing are
+/// structural, comparing the operators, operands and values. Expressions are
+/// ordered by value, then by node count, and then structurally, which gives a
+/// canonical order for sorting results.
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        from = "ExpressionRepr<T>",
+        into = "ExpressionRepr<T>",
+        bound(
+            serialize = "T: serde::Serialize",
+            deserialize = "T: serde::de::DeserializeOwned"
+        )
+    )
+)]
 pub enum Expression<T: NumberType> {
     /// Stores a value
     Value(T),
     /// Stores the application of an operator over two expressions and the
-    /// result there-of
+    /// result there-of. The result is always the one given by the number
+    /// system which built the expression, after any reduction it does, so it
+    /// equals [`re_eval`](Expression::re_eval) under that system.
     Application(T, Operator, Box<Expression<T>>, Box<Expression<T>>),
 }
+/// The serialized form of an [`Expression`], which names the fields of an
+/// application. Deserializing trusts the stored values.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+enum ExpressionRepr<T: NumberType> {
+    Value(T),
+    Application {
+        value: T,
+        op: Operator,
+        left: Box<ExpressionRepr<T>>,
+        right: Box<ExpressionRepr<T>>,
+    },
+}
+#[cfg(feature = "serde")]
+impl<T: NumberType> From<Expression<T>> for ExpressionRepr<T> {
+    fn from(value: Expression<T>) -> Self {
+        match value {
+            | Expression::Value(t) => ExpressionRepr::Value(t),
+            | Expression::Application(value, op, left, right) => {
+                ExpressionRepr::Application {
+                    value,
+                    op,
+                    left: Box::new((*left).into()),
+                    right: Box::new((*right).into()),
+                }
+            }
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl<T: NumberType> From<ExpressionRepr<T>> for Expression<T> {
+    fn from(value: ExpressionRepr<T>) -> Self {
+        match value {
+            | ExpressionRepr::Value(t) => Expression::Value(t),
+            | ExpressionRepr::Application {
+                value,
+                op,
+                left,
+                right,
+            } => {
+                Expression::Application(
+                    value,
+                    op,
+                    Box::new((*left).into()),
+                    Box::new((*right).into()),
+                )
+            }
+        }
+    }
+}
 impl<T: NumberType> Debug for Expression<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             | Self::Value(arg0) => write!(f, "Val {arg0:?}"),
             | Self::Application(_, op, left, right) => {
@@ -164,8 +448,46 @@ impl<T: NumberType> Debug for Expression<T> {
         }
     }
 }
+impl<T: NumberType> PartialOrd for Expression<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: NumberType> Ord for Expression<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.get_value()
+            .cmp(other.get_value())
+            .then_with(|| self.node_count().cmp(&other.node_count()))
+            .then_with(|| {
+                match (self, other) {
+                    | (Expression::Value(_), Expression::Value(_)) => {
+                        Ordering::Equal
+                    }
+                    | (Expression::Value(_), Expression::Application(..)) => {
+                        Ordering::Less
+                    }
+                    | (Expression::Application(..), Expression::Value(_)) => {
+                        Ordering::Greater
+                    }
+                    | (
+                        Expression::Application(_, op, left, right),
+                        Expression::Application(
+                            _,
+                            other_op,
+                            other_left,
+                            other_right,
+                        ),
+                    ) => {
+                        op.cmp(other_op)
+                            .then_with(|| left.cmp(other_left))
+                            .then_with(|| right.cmp(other_right))
+                    }
+                }
+            })
+    }
+}
 impl<T: NumberType> Display for Expression<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             | Expression::Value(t) => Display::fmt(t, f),
             | Expression::Application(_, operator, left, right) => {
@@ -193,33 +515,70 @@ impl<T: NumberType> Expression<T> {
             | Expression::Application(t, ..) => t,
         }
     }
-    pub fn is_valid(&self) -> bool {
+    /// Gets the operator applied at the root, or `None` for a value
+    pub fn root_operator(&self) -> Option<Operator> {
+        match self {
+            | Expression::Value(_) => None,
+            | Expression::Application(_, operator, ..) => Some(*operator),
+        }
+    }
+    /// Gets the left operand of the root, or `None` for a value
+    pub fn left(&self) -> Option<&Expression<T>> {
+        match self {
+            | Expression::Value(_) => None,
+            | Expression::Application(_, _, left, _) => Some(left),
+        }
+    }
+    /// Gets the right operand of the root, or `None` for a value
+    pub fn right(&self) -> Option<&Expression<T>> {
+        match self {
+            | Expression::Value(_) => None,
+            | Expression::Application(_, _, _, right) => Some(right),
+        }
+    }
+    /// Checks whether the root is an application the generator would build
+    /// in the system: its value is a value of the system which it keeps, see
+    /// [`NumberSystem::keeps_result`], and its operands are not a redundant
+    /// nesting, see [`is_valid_application`](Self::is_valid_application).
+    pub fn is_valid_in<N: NumberSystem<T>>(&self, system: &N) -> bool {
         match self {
             | Expression::Value(_) => true,
-            | Expression::Application(value, operator, _, expr_right) => {
-                if *value == T::ZERO {
-                    return false;
-                }
-                match operator {
-                    | Operator::Add | Operator::Mul => {
-                        match expr_right.as_ref() {
-                            | Expression::Value(_) => {}
-                            | Expression::Application(_, right_oper, _, _) => {
-                                if right_oper == operator {
-                                    return false;
-                                }
-                            }
-                        }
-                    }
-                    | Operator::Sub => {}
-                    | Operator::Div => {}
-                }
-
-                true
+            | Expression::Application(
+                value,
+                operator,
+                expr_left,
+                expr_right,
+            ) => {
+                system.keeps_result(*value)
+                    && system.check_value(*value).is_ok()
+                    && Self::is_valid_application(
+                        system,
+                        *expr_left.get_value(),
+                        *operator,
+                        expr_right,
+                    )
             }
         }
     }
+    /// Checks whether applying the operator to a left operand of value
+    /// `left_value` and the given right operand would be valid in the system,
+    /// without having to construct it. This does not check the value of the
+    /// application. Chains such as `a + (b + c)` or `a - (b + c)` are only
+    /// valid when nested to the left, see [`Operator::nests_redundantly`],
+    /// unless the system does not build the left-nested form.
+    pub fn is_valid_application<N: NumberSystem<T>>(
+        system: &N,
+        left_value: T,
+        operator: Operator,
+        expr_right: &Expression<T>,
+    ) -> bool {
+        valid_application(system, left_value, operator, expr_right)
+    }
     /// Re evaluate the value of the expression
+    ///
+    /// # Panics
+    /// Panics if an application is undefined in the system, see
+    /// [`try_eval`](Self::try_eval).
     pub fn re_eval<N: NumberSystem<T>>(&self, system: &N) -> T {
         match self {
             | Expression::Value(t) => *t,
@@ -230,8 +589,1326 @@ impl<T: NumberType> Expression<T> {
             }
         }
     }
-    /// A test helper to check whether evaluation is correct
+    /// Re-evaluates the expression like [`re_eval`](Self::re_eval), but
+    /// returns the first application (from the left, innermost first) which
+    /// is undefined in the system instead of panicking.
+    pub fn try_eval<N: NumberSystem<T>>(
+        &self,
+        system: &N,
+    ) -> Result<T, &Expression<T>> {
+        match self {
+            | Expression::Value(t) => Ok(*t),
+            | Expression::Application(_, operator, left, right) => {
+                let (left, right) =
+                    (left.try_eval(system)?, right.try_eval(system)?);
+                operator.apply(system, left, right).ok_or(self)
+            }
+        }
+    }
+    /// Checks whether the stored value matches a re-evaluation of the
+    /// expression. An expression which cannot be evaluated in the system
+    /// does not match.
     pub fn check<N: NumberSystem<T>>(&self, system: &N) -> bool {
-        self.re_eval(system) == *self.get_value()
+        self.try_eval(system) == Ok(*self.get_value())
+    }
+    /// Counts every value and application node in the expression
+    pub fn node_count(&self) -> usize {
+        match self {
+            | Expression::Value(_) => 1,
+            | Expression::Application(_, _, left, right) => {
+                1 + left.node_count() + right.node_count()
+            }
+        }
+    }
+    /// The length of the longest path from the root to a value, where a
+    /// single value has depth 1
+    pub fn depth(&self) -> usize {
+        match self {
+            | Expression::Value(_) => 1,
+            | Expression::Application(_, _, left, right) => {
+                1 + left.depth().max(right.depth())
+            }
+        }
+    }
+    /// Draws the expression as an indented tree with one node per line, where
+    /// each application shows its operator and value above its operands.
+    /// ```text
+    /// * = 30
+    /// |-- + = 10
+    /// |   |-- 6
+    /// |   `-- 4
+    /// `-- 3
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        let mut tree = String::new();
+        self.push_tree(&mut tree, "", "");
+        tree
+    }
+    /// Writes this node after `first` and its children after `rest`.
+    fn push_tree(&self, tree: &mut String, first: &str, rest: &str) {
+        tree.push_str(first);
+        match self {
+            | Expression::Value(t) => tree.push_str(&format!("{t}\n")),
+            | Expression::Application(t, operator, left, right) => {
+                tree.push_str(&format!("{operator} = {t}\n"));
+                left.push_tree(
+                    tree,
+                    &format!("{rest}|-- "),
+                    &format!("{rest}|   "),
+                );
+                right.push_tree(
+                    tree,
+                    &format!("{rest}`-- "),
+                    &format!("{rest}    "),
+                );
+            }
+        }
+    }
+    /// Reorders the operands of every commutative operator so the greater one
+    /// (in the [`Ord`] order) comes first, like
+    /// [`NormalNumberSystem`](super::numbers::NormalNumberSystem) does.
+    /// Expressions which only differ by swapped commutative operands then
+    /// canonicalize to equal trees.
+    pub fn canonicalize(&self) -> Expression<T> {
+        match self {
+            | Expression::Value(t) => Expression::Value(*t),
+            | Expression::Application(t, operator, left, right) => {
+                let (mut left, mut right) =
+                    (left.canonicalize(), right.canonicalize());
+                if operator.is_commutative() && left < right {
+                    (left, right) = (right, left);
+                }
+                Expression::Application(
+                    *t,
+                    *operator,
+                    Box::new(left),
+                    Box::new(right),
+                )
+            }
+        }
+    }
+    /// Scores the expression by its size, the operators it uses and the
+    /// number of digits of its largest intermediate result, weighted by
+    /// `weights`. A single value has no intermediate results.
+    pub fn score(&self, weights: &ScoreWeights) -> i64 {
+        let operators: i64 = operator_histogram(self)
+            .iter()
+            .zip(weights.operators)
+            .map(|(count, weight)| *count as i64 * weight)
+            .sum();
+        // Finding the digits walks the whole tree, so skip it when unused.
+        let magnitude = match weights.magnitude {
+            | 0 => 0,
+            | weight => self.max_intermediate_digits() as i64 * weight,
+        };
+        self.node_count() as i64 * weights.node_count + operators + magnitude
+    }
+    /// The largest absolute value of any node, leaves included, which shows
+    /// how large the numbers got on the way to the result.
+    pub fn max_intermediate(&self) -> T {
+        match self {
+            | Expression::Value(t) => abs(*t),
+            | Expression::Application(t, _, left, right) => {
+                abs(*t)
+                    .max(left.max_intermediate())
+                    .max(right.max_intermediate())
+            }
+        }
+    }
+    /// The most digits of any value computed by an application.
+    fn max_intermediate_digits(&self) -> usize {
+        match self {
+            | Expression::Value(_) => 0,
+            | Expression::Application(t, _, left, right) => {
+                digits(*t)
+                    .max(left.max_intermediate_digits())
+                    .max(right.max_intermediate_digits())
+            }
+        }
+    }
+    /// Rebuilds the tree with `f` applied to every stored value, both the
+    /// leaves and the results of applications. Nothing is re-evaluated, so
+    /// the results are only right if `f` preserves the arithmetic (or the
+    /// caller calls [`check`](Self::check) afterwards).
+    pub fn map_values<U: NumberType, F: Fn(&T) -> U>(
+        &self,
+        f: &F,
+    ) -> Expression<U> {
+        match self {
+            | Expression::Value(t) => Expression::Value(f(t)),
+            | Expression::Application(t, operator, left, right) => {
+                Expression::Application(
+                    f(t),
+                    *operator,
+                    Box::new(left.map_values(f)),
+                    Box::new(right.map_values(f)),
+                )
+            }
+        }
+    }
+    /// Every application in the expression paired with its result, in the
+    /// order they are evaluated: both operands come before the application
+    /// which uses them. Values are not steps.
+    pub fn steps(&self) -> Vec<(Expression<T>, T)> {
+        let mut steps = Vec::new();
+        self.push_steps(&mut steps);
+        steps
+    }
+    fn push_steps(&self, steps: &mut Vec<(Expression<T>, T)>) {
+        if let Expression::Application(t, _, left, right) = self {
+            left.push_steps(steps);
+            right.push_steps(steps);
+            steps.push((self.clone(), *t));
+        }
+    }
+    /// The values at the leaves of the expression from left to right, which
+    /// are the source numbers it uses
+    pub fn leaves(&self) -> Vec<T> {
+        let mut leaves = Vec::new();
+        self.push_leaves(&mut leaves);
+        leaves
+    }
+    fn push_leaves(&self, leaves: &mut Vec<T>) {
+        match self {
+            | Expression::Value(t) => leaves.push(*t),
+            | Expression::Application(_, _, left, right) => {
+                left.push_leaves(leaves);
+                right.push_leaves(leaves);
+            }
+        }
+    }
+    /// Counts how many times each source number is used by the expression
+    pub fn uses_multiset(&self) -> BTreeMap<T, usize> {
+        let mut counts = BTreeMap::new();
+        for leaf in self.leaves() {
+            *counts.entry(leaf).or_insert(0) += 1;
+        }
+        counts
+    }
+    /// Whether the expression only uses numbers from `available`, each at
+    /// most as many times as it is available. This guards against solutions
+    /// which were not built from the given source numbers.
+    pub fn is_legal(&self, available: &BTreeMap<T, usize>) -> bool {
+        self.uses_multiset().iter().all(|(number, count)| {
+            available.get(number).is_some_and(|a| a >= count)
+        })
+    }
+    /// Formats the expression in infix notation with only the parentheses
+    /// required by precedence and associativity, e.g. `(6 + 4) * 3`. Chains
+    /// are read left to right, so a right operand of the same precedence is
+    /// only left bare for `a + (b + c)` and `a * (b * c)`.
+    pub fn to_minimal_string(&self) -> String {
+        match self {
+            | Expression::Value(t) => t.to_string(),
+            | Expression::Application(_, operator, left, right) => {
+                let wrap = |child: &Expression<T>, is_right: bool| {
+                    match child {
+                        | Expression::Application(_, op, ..)
+                            if operator.needs_parentheses(*op, is_right) =>
+                        {
+                            format!("({})", child.to_minimal_string())
+                        }
+                        | _ => child.to_minimal_string(),
+                    }
+                };
+                let (left, right) = (wrap(left, false), wrap(right, true));
+                format!("{left} {operator} {right}")
+            }
+        }
+    }
+    /// Formats the expression in reverse-Polish (postfix) notation, e.g.
+    /// `6 4 + 3 *`
+    pub fn to_postfix(&self) -> String {
+        match self {
+            | Expression::Value(t) => t.to_string(),
+            | Expression::Application(_, operator, left, right) => {
+                format!(
+                    "{} {} {operator}",
+                    left.to_postfix(),
+                    right.to_postfix()
+                )
+            }
+        }
+    }
+}
+/// Weights for [`Expression::score`], where a lower score is a more preferred
+/// expression. Every weight starts at zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScoreWeights {
+    node_count: i64,
+    operators: [i64; OPERATOR_COUNT],
+    magnitude: i64,
+}
+impl ScoreWeights {
+    /// The cost of every value and application node.
+    pub fn node_count(mut self, weight: i64) -> Self {
+        self.node_count = weight;
+        self
+    }
+    /// The cost of each use of `op`.
+    pub fn operator(mut self, op: Operator, weight: i64) -> Self {
+        self.operators[op.index()] = weight;
+        self
+    }
+    /// The cost of each decimal digit of the largest intermediate value.
+    pub fn magnitude(mut self, weight: i64) -> Self {
+        self.magnitude = weight;
+        self
+    }
+}
+/// The absolute value of `t`. The most negative value of a signed type has
+/// no positive counterpart, so it saturates where the type allows.
+fn abs<T: NumberType>(t: T) -> T {
+    match t < T::ZERO {
+        | true => T::ZERO.saturating_sub(t).unwrap_or(t),
+        | false => t,
+    }
+}
+/// The parts of a tree which the generator reads and builds, so that the same
+/// rules decide what is generated for owned [`Expression`]s and for shared
+/// [`RcExpression`](super::rc_expression::RcExpression)s. Without `std`
+/// there is no generator, so only the validity rules read them.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) trait ExpressionNode<T: NumberType>: Sized {
+    /// A single value
+    fn leaf(value: T) -> Self;
+    /// The application of `operator` to the operands, which evaluates to
+    /// `value`
+    fn application(
+        value: T,
+        operator: Operator,
+        left: &Self,
+        right: &Self,
+    ) -> Self;
+    /// Gets the value of the node
+    fn value(&self) -> T;
+    /// Gets the operator and the operands of an application, or `None` for
+    /// a value
+    fn operands(&self) -> Option<(Operator, &Self, &Self)>;
+}
+impl<T: NumberType> ExpressionNode<T> for Expression<T> {
+    fn leaf(value: T) -> Self {
+        Expression::Value(value)
+    }
+
+    fn application(
+        value: T,
+        operator: Operator,
+        left: &Self,
+        right: &Self,
+    ) -> Self {
+        Expression::Application(
+            value,
+            operator,
+            Box::new(left.clone()),
+            Box::new(right.clone()),
+        )
+    }
+
+    fn value(&self) -> T {
+        *self.get_value()
+    }
+
+    fn operands(&self) -> Option<(Operator, &Self, &Self)> {
+        match self {
+            | Expression::Value(_) => None,
+            | Expression::Application(_, operator, left, right) => {
+                Some((*operator, left, right))
+            }
+        }
+    }
+}
+/// Whether an application of `operator` to `left_value` and the given right
+/// operand is valid, see [`Expression::is_valid_application`].
+pub(crate) fn valid_application<
+    T: NumberType,
+    N: NumberSystem<T>,
+    E: ExpressionNode<T>,
+>(
+    system: &N,
+    left_value: T,
+    operator: Operator,
+    expr_right: &E,
+) -> bool {
+    match expr_right.operands() {
+        | Some((right_oper, middle, last))
+            if operator.nests_redundantly(right_oper) =>
+        {
+            let values = [left_value, middle.value(), last.value()];
+            !builds_left_nested(system, operator, values)
+        }
+        | _ => true,
+    }
+}
+/// Whether the system builds `(a operator b) operator c` in some form, where
+/// `a`, `b` and `c` are the values. Each of the two applications is built
+/// whenever its value is defined and kept, since if it is redundant itself its
+/// own left-nested form is built instead.
+fn builds_left_nested<T: NumberType, N: NumberSystem<T>>(
+    system: &N,
+    operator: Operator,
+    [a, b, c]: [T; 3],
+) -> bool {
+    let kept = |value: Option<T>| value.filter(|t| system.keeps_result(*t));
+    kept(operator.apply(system, a, b))
+        .and_then(|inner| kept(operator.apply(system, inner, c)))
+        .is_some()
+}
+/// The number of decimal digits of the whole part of `t`, ignoring its sign.
+/// A power of ten is grown until it passes `t`, rather than dividing `t` down,
+/// which would never reach zero for a fraction.
+fn digits<T: NumberType>(t: T) -> usize {
+    let ten = [T::ONE; 10].into_iter().sum::<T>();
+    let magnitude = abs(t);
+    let (mut digits, mut power) = (1, ten);
+    while power <= magnitude {
+        digits += 1;
+        // The next power does not fit, so nothing can reach it.
+        let Some(next) = power.checked_mul(ten) else {
+            break;
+        };
+        power = next;
+    }
+    digits
+}
+/// Counts how often each operator occurs in the expression, indexed by
+/// [`Operator::index`]. A single value uses no operators.
+pub fn operator_histogram<T: NumberType>(
+    expr: &Expression<T>,
+) -> [usize; OPERATOR_COUNT] {
+    let mut histogram = [0; OPERATOR_COUNT];
+    add_to_histogram(expr, &mut histogram);
+    histogram
+}
+fn add_to_histogram<T: NumberType>(
+    expr: &Expression<T>,
+    histogram: &mut [usize; OPERATOR_COUNT],
+) {
+    if let Expression::Application(_, operator, left, right) = expr {
+        histogram[operator.index()] += 1;
+        add_to_histogram(left, histogram);
+        add_to_histogram(right, histogram);
+    }
+}
+/// Sums the operator histograms of all the expressions.
+pub fn total_operator_histogram<'a, T: NumberType>(
+    exprs: impl IntoIterator<Item = &'a Expression<T>>,
+) -> [usize; OPERATOR_COUNT] {
+    let mut histogram = [0; OPERATOR_COUNT];
+    for expr in exprs {
+        add_to_histogram(expr, &mut histogram);
+    }
+    histogram
+}
+
+#[test]
+fn test_postfix() {
+    let expr = Expression::Application(
+        30u32,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            10,
+            Operator::Add,
+            Box::new(Expression::Value(6)),
+            Box::new(Expression::Value(4)),
+        )),
+        Box::new(Expression::Value(3)),
+    );
+    assert_eq!(expr.to_postfix(), "6 4 + 3 *");
+    assert_eq!(Expression::Value(7u32).to_postfix(), "7");
+}
+#[test]
+fn test_minimal_string() {
+    let value = |t: u32| Box::new(Expression::Value(t));
+    let apply = |operator: Operator, left, right| {
+        Box::new(Expression::Application(0u32, operator, left, right))
+    };
+    let cases = [
+        (
+            apply(
+                Operator::Mul,
+                apply(Operator::Add, value(6), value(4)),
+                value(3),
+            ),
+            "(6 + 4) * 3",
+        ),
+        (
+            apply(
+                Operator::Sub,
+                value(9),
+                apply(Operator::Sub, value(5), value(2)),
+            ),
+            "9 - (5 - 2)",
+        ),
+        (
+            apply(
+                Operator::Sub,
+                apply(Operator::Sub, value(9), value(5)),
+                value(2),
+            ),
+            "9 - 5 - 2",
+        ),
+        (
+            apply(
+                Operator::Div,
+                value(8),
+                apply(Operator::Mul, value(2), value(2)),
+            ),
+            "8 / (2 * 2)",
+        ),
+        (
+            apply(
+                Operator::Add,
+                value(1),
+                apply(Operator::Mul, value(2), value(3)),
+            ),
+            "1 + 2 * 3",
+        ),
+        (
+            apply(
+                Operator::Add,
+                value(1),
+                apply(Operator::Add, value(2), value(3)),
+            ),
+            "1 + 2 + 3",
+        ),
+        (
+            apply(
+                Operator::Add,
+                value(1),
+                apply(Operator::Sub, value(3), value(2)),
+            ),
+            "1 + (3 - 2)",
+        ),
+        (
+            apply(
+                Operator::Pow,
+                apply(Operator::Mul, value(2), value(3)),
+                value(2),
+            ),
+            "(2 * 3) ^ 2",
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(expr.to_minimal_string(), expected);
+    }
+    assert_eq!(value(5).to_minimal_string(), "5");
+}
+#[test]
+fn test_operator_histogram() {
+    let expr = Expression::Application(
+        27u32,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            9,
+            Operator::Add,
+            Box::new(Expression::Value(6)),
+            Box::new(Expression::Value(3)),
+        )),
+        Box::new(Expression::Application(
+            3,
+            Operator::Add,
+            Box::new(Expression::Value(2)),
+            Box::new(Expression::Value(1)),
+        )),
+    );
+    assert_eq!(operator_histogram(&expr), [2, 0, 1, 0, 0, 0, 0]);
+    assert_eq!(
+        operator_histogram(&Expression::Value(3u32)),
+        [0; OPERATOR_COUNT]
+    );
+    assert_eq!(
+        total_operator_histogram([&expr, &expr, &Expression::Value(1)]),
+        [4, 0, 2, 0, 0, 0, 0]
+    );
+    assert_eq!(Operator::Div.index(), 3);
+}
+#[test]
+fn test_structural_hash() {
+    use std::collections::HashSet;
+    let sum = |left: u32, right: u32| {
+        Expression::Application(
+            left + right,
+            Operator::Add,
+            Box::new(Expression::Value(left)),
+            Box::new(Expression::Value(right)),
+        )
+    };
+    let mut set = HashSet::new();
+    set.insert(sum(1, 1));
+    set.insert(sum(1, 1).clone());
+    set.insert(sum(2, 1));
+    set.insert(sum(1, 2));
+    set.insert(Expression::Value(2));
+    set.insert(Expression::Value(3));
+    assert_eq!(set.len(), 5);
+    assert_ne!(sum(2, 1), sum(1, 2));
+}
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let expr = Expression::Application(
+        30u32,
+        Operator::Mul,
+        Box::new(Expression::Value(10)),
+        Box::new(Expression::Value(3)),
+    );
+    let json = serde_json::to_value(&expr).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"Application": {
+            "value": 30,
+            "op": "Mul",
+            "left": {"Value": 10},
+            "right": {"Value": 3},
+        }})
+    );
+    assert_eq!(
+        serde_json::from_value::<Expression<u32>>(json).unwrap(),
+        expr
+    );
+}
+#[test]
+fn test_node_count_and_depth() {
+    let expr = Expression::Application(
+        30u32,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            10,
+            Operator::Add,
+            Box::new(Expression::Value(6)),
+            Box::new(Expression::Value(4)),
+        )),
+        Box::new(Expression::Value(3)),
+    );
+    assert_eq!(expr.node_count(), 5);
+    assert_eq!(expr.depth(), 3);
+    assert_eq!(Expression::Value(7u32).node_count(), 1);
+    assert_eq!(Expression::Value(7u32).depth(), 1);
+}
+
+/// Parses with normal arithmetic, but without the operand rules the
+/// generator uses to prune its search, so `4 + 6`, `3 * 3` and `2 * 1` are
+/// all accepted. Use [`Expression::parse_with`] to parse with a stricter
+/// number system.
+#[cfg(feature = "parsing")]
+impl<T: NumberType + Parsable> Parsable for Expression<T> {
+    fn parse(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+    ) -> Result<Self, String> {
+        let relaxed = NormalNumberSystem::builder()
+            .allow_zero_operands(true)
+            .allow_identity_operands(true)
+            .allow_commutative_duplicates(true)
+            .allow_negative_results(true);
+        Self::parse_with(tokens, &relaxed)
+    }
+}
+#[cfg(feature = "parsing")]
+impl<T: NumberType + Parsable> Expression<T> {
+    /// Parses an infix expression, computing the values of every application
+    /// using the given number system.
+    pub fn parse_with<N: NumberSystem<T>>(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+        system: &N,
+    ) -> Result<Self, String> {
+        Self::parse_chain(
+            tokens,
+            system,
+            &[Operator::Add, Operator::Sub],
+            Self::parse_product,
+        )
+    }
+    /// Parses a product, which binds tighter than a sum.
+    fn parse_product<N: NumberSystem<T>>(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+        system: &N,
+    ) -> Result<Self, String> {
+        Self::parse_chain(
+            tokens,
+            system,
+            &[Operator::Mul, Operator::Div],
+            Self::parse_power,
+        )
+    }
+    /// Parses powers and concatenations, which bind tighter than a product.
+    fn parse_power<N: NumberSystem<T>>(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+        system: &N,
+    ) -> Result<Self, String> {
+        Self::parse_chain(
+            tokens,
+            system,
+            &[Operator::Pow, Operator::Concat],
+            Self::parse_atom,
+        )
+    }
+    /// Parses either a number or a parenthesised expression.
+    fn parse_atom<N: NumberSystem<T>>(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+        system: &N,
+    ) -> Result<Self, String> {
+        match tokens.front() {
+            | Some(Token::Punctuation('(')) => {
+                tokens.pop_front();
+                let expr = Self::parse_with(tokens, system)?;
+                match tokens.pop_front() {
+                    | Some(Token::Punctuation(')')) => Ok(expr),
+                    | a => Err(format!("Expected ')' found {a:?}")),
+                }
+            }
+            | _ => Ok(Expression::Value(T::parse(tokens)?)),
+        }
+    }
+    /// Parses a left associative chain of the given operators over operands
+    /// read by `operand`.
+    fn parse_chain<N: NumberSystem<T>>(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+        system: &N,
+        operators: &[Operator],
+        operand: fn(
+            &mut alloc::collections::VecDeque<Token>,
+            &N,
+        ) -> Result<Self, String>,
+    ) -> Result<Self, String> {
+        let mut left = operand(tokens, system)?;
+        while let Some((operator, length)) = Self::peek_operator(tokens)
+            .filter(|(operator, _)| operators.contains(operator))
+        {
+            tokens.drain(..length);
+            let right = operand(tokens, system)?;
+            let value = operator
+                .apply(system, *left.get_value(), *right.get_value())
+                .ok_or_else(|| {
+                    format!("Could not evaluate {left:#} {operator} {right:#}")
+                })?;
+            left = Expression::Application(
+                value,
+                operator,
+                Box::new(left),
+                Box::new(right),
+            );
+        }
+        Ok(left)
+    }
+    /// Gets the operator at the front of the tokens and the number of tokens
+    /// it spans without consuming it.
+    fn peek_operator(
+        tokens: &alloc::collections::VecDeque<Token>,
+    ) -> Option<(Operator, usize)> {
+        let mut front: alloc::collections::VecDeque<Token> =
+            tokens.iter().take(2).cloned().collect();
+        let length = front.len();
+        let operator = Operator::parse(&mut front).ok()?;
+        Some((operator, length - front.len()))
     }
 }
+
+#[test]
+fn test_operator_iterator_round_trips() {
+    let all: Vec<Operator> = Operators::EVERY.into_iter().collect();
+    assert_eq!(
+        all,
+        [
+            Operator::Add,
+            Operator::Sub,
+            Operator::Mul,
+            Operator::Div,
+            Operator::Pow,
+            Operator::Concat,
+            Operator::Mod
+        ]
+    );
+    let some = Operators::from_iter([Operator::Pow, Operator::Sub]);
+    assert_eq!(
+        some.into_iter().collect::<Vec<_>>(),
+        [Operator::Sub, Operator::Pow]
+    );
+}
+#[test]
+fn test_pow() {
+    use super::numbers::NormalNumberSystem;
+    assert_eq!(
+        Operator::Pow.apply(&NormalNumberSystem, 2u32, 10),
+        Some(1024)
+    );
+    assert_eq!(Operator::Pow.apply(&NormalNumberSystem, 2u8, 10), None);
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_pow() {
+    use crate::parsing::token_reader::read;
+    for s in ["^", "pow", "POW"] {
+        let mut tokens = read(s.to_string()).unwrap().into();
+        assert_eq!(Operator::parse(&mut tokens), Ok(Operator::Pow));
+    }
+}
+#[test]
+fn test_concat() {
+    use super::numbers::NormalNumberSystem;
+    assert_eq!(
+        Operator::Concat.apply(&NormalNumberSystem, 2u32, 5),
+        Some(25)
+    );
+    assert_eq!(
+        Operator::Concat.apply(&NormalNumberSystem, 5u32, 2),
+        Some(52)
+    );
+    assert_eq!(
+        Operator::Concat.apply(&NormalNumberSystem, 10u32, 0),
+        Some(100)
+    );
+    assert_eq!(
+        Operator::Concat.apply(&NormalNumberSystem, 25u8, 5),
+        Some(255)
+    );
+    assert_eq!(Operator::Concat.apply(&NormalNumberSystem, 25u8, 6), None);
+    assert_eq!(Operator::Concat.apply(&NormalNumberSystem, 2u8, 100), None);
+    let expr = Expression::Application(
+        25u32,
+        Operator::Concat,
+        Box::new(Expression::Value(2)),
+        Box::new(Expression::Application(
+            5,
+            Operator::Concat,
+            Box::new(Expression::Value(0)),
+            Box::new(Expression::Value(5)),
+        )),
+    );
+    assert!(expr.is_valid_in(&NormalNumberSystem));
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_concat() {
+    use crate::parsing::token_reader::read;
+    for s in ["||", "concat"] {
+        let mut tokens = read(s.to_string()).unwrap().into();
+        assert_eq!(Operator::parse(&mut tokens), Ok(Operator::Concat));
+    }
+    let mut tokens = read("2 || 5 * 2".to_string()).unwrap().into();
+    let expr = Expression::<u32>::parse(&mut tokens).unwrap();
+    assert_eq!(*expr.get_value(), 50);
+    assert_eq!(expr.to_postfix(), "2 5 || 2 *");
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_expression() {
+    use crate::parsing::token_reader::read;
+    let parse =
+        |s: &str| Expression::<u32>::parse(&mut read(s.to_string())?.into());
+    let expr = parse("(6 + 4) * 3").unwrap();
+    assert_eq!(*expr.get_value(), 30);
+    assert_eq!(expr.to_string(), "(6 + 4) * 3");
+    let expr = parse("10 - 9 / 3 * 2").unwrap();
+    assert_eq!(*expr.get_value(), 4);
+    assert_eq!(
+        parse(&expr.to_string()).unwrap().to_string(),
+        expr.to_string()
+    );
+    let exprs = Vec::<Expression<i32>>::parse_line("[5 - 3, -5]").unwrap();
+    assert_eq!(*exprs[0].get_value(), 2);
+    assert_eq!(exprs[0].to_postfix(), "5 3 -");
+    assert_eq!(*exprs[1].get_value(), -5);
+    assert!(parse("(6 + 4").is_err());
+    assert!(parse("6 + ").is_err());
+    assert!(parse("7 / 2").is_err());
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_trailing_tokens() {
+    let parse = Expression::<u32>::parse_line;
+    assert_eq!(
+        parse("6 4"),
+        Err(String::from(
+            "at column 2: Expected the end of the line found Number(\"4\")"
+        ))
+    );
+    assert_eq!(
+        parse("7 garbage"),
+        Err(String::from(
+            "at column 2: Expected the end of the line found \
+             Word(\"garbage\")"
+        ))
+    );
+    assert_eq!(
+        parse("6 + 4 )"),
+        Err(String::from(
+            "at column 6: Expected the end of the line found \
+             Punctuation(')')"
+        ))
+    );
+    assert!(parse("6 + 4 5").is_err_and(|e| e.starts_with("at column 6:")));
+    assert_eq!(parse("(6 + 4) * 3").map(|e| *e.get_value()), Ok(30));
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_ignores_generator_rules() {
+    use super::numbers::NormalNumberSystem;
+    use crate::parsing::token_reader::read;
+    let value =
+        |s: &str| Expression::<i32>::parse_line(s).map(|e| *e.get_value());
+    assert_eq!(value("4 + 6"), Ok(10));
+    assert_eq!(value("3 * 3"), Ok(9));
+    assert_eq!(value("2 * 3 + 1"), Ok(7));
+    assert_eq!(value("5 - 0 * 2"), Ok(5));
+    assert_eq!(value("3 - 5"), Ok(-2));
+    assert!(value("7 / 2").is_err());
+    let mut tokens = read(String::from("4 + 6")).unwrap().into();
+    assert!(
+        Expression::<u32>::parse_with(&mut tokens, &NormalNumberSystem)
+            .is_err()
+    );
+}
+#[test]
+fn test_operator_subsets() {
+    // Every operator is either in or out, less the empty set.
+    assert_eq!(
+        Operators::EVERY.subsets().count(),
+        (1 << OPERATOR_COUNT) - 1
+    );
+    let basic = Operators::from_iter([
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+    ]);
+    assert_eq!(basic.subsets().count(), 15);
+    for subset in basic.subsets() {
+        assert!(subset.into_iter().all(|op| basic.contains(op)));
+    }
+    assert_eq!(Operators::NONE.subsets().count(), 0);
+}
+#[test]
+fn test_operators_set_operations() {
+    assert_eq!(Operators::EVERY.len(), OPERATOR_COUNT);
+    let mut operators = Operators::NONE;
+    assert!(operators.is_empty());
+    operators.insert(Operator::Mul);
+    operators.insert(Operator::Mul);
+    assert_eq!(operators, Operators::from_iter([Operator::Mul]));
+    assert!(operators.contains(Operator::Mul));
+    assert!(!operators.contains(Operator::Add));
+    operators.remove(Operator::Mul);
+    operators.remove(Operator::Mul);
+    assert!(operators.is_empty());
+    let mut all = Operators::EVERY;
+    all.remove(Operator::Pow);
+    all.remove(Operator::Concat);
+    all.remove(Operator::Mod);
+    assert_eq!(all, Operators::ALL);
+    assert_eq!(
+        !Operators::ALL,
+        Operators::from_iter([Operator::Pow, Operator::Concat, Operator::Mod])
+    );
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_operators() {
+    assert_eq!(
+        Operators::parse_line("+*"),
+        Ok(Operators::from_iter([Operator::Add, Operator::Mul]))
+    );
+    assert_eq!(Operators::parse_line("+-*/"), Ok(Operators::ALL));
+    assert_eq!(Operators::parse_line("+-*/^||%"), Ok(Operators::EVERY));
+    assert!(Operators::parse_line("").is_err());
+    assert!(Operators::parse_line("+&").is_err());
+    assert_eq!(
+        Vec::<Operator>::parse_line("[+, *]"),
+        Ok(vec![Operator::Add, Operator::Mul])
+    );
+}
+#[test]
+fn test_leaves() {
+    let expr = Expression::Application(
+        30u32,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            10,
+            Operator::Add,
+            Box::new(Expression::Value(6)),
+            Box::new(Expression::Value(4)),
+        )),
+        Box::new(Expression::Value(3)),
+    );
+    assert_eq!(expr.leaves(), [6, 4, 3]);
+    assert_eq!(Expression::Value(7u32).leaves(), [7]);
+    let doubled = Expression::Application(
+        60u32,
+        Operator::Add,
+        Box::new(expr.clone()),
+        Box::new(expr),
+    );
+    assert_eq!(
+        doubled.uses_multiset(),
+        BTreeMap::from([(3, 2), (4, 2), (6, 2)])
+    );
+}
+#[test]
+fn test_operator_properties() {
+    use Operator::*;
+    for (operator, commutative, associative) in [
+        (Add, true, true),
+        (Sub, false, false),
+        (Mul, true, true),
+        (Div, false, false),
+        (Pow, false, false),
+        (Concat, false, false),
+        (Mod, false, false),
+    ] {
+        assert_eq!(operator.is_commutative(), commutative, "{operator}");
+        assert_eq!(operator.is_associative(), associative, "{operator}");
+    }
+}
+#[test]
+fn test_tree_string() {
+    let expr = Expression::Application(
+        30u32,
+        Operator::Mul,
+        Box::new(Expression::Application(
+            10,
+            Operator::Add,
+            Box::new(Expression::Value(6)),
+            Box::new(Expression::Value(4)),
+        )),
+        Box::new(Expression::Application(
+            3,
+            Operator::Div,
+            Box::new(Expression::Value(9)),
+            Box::new(Expression::Value(3)),
+        )),
+    );
+    let expected = "\
+* = 30
+|-- + = 10
+|   |-- 6
+|   `-- 4
+`-- / = 3
+    |-- 9
+    `-- 3
+";
+    assert_eq!(expr.to_tree_string(), expected);
+    assert_eq!(Expression::Value(7u32).to_tree_string(), "7\n");
+}
+#[test]
+fn test_operators_display() {
+    assert_eq!(Operators::ALL.to_string(), "+-*/");
+    assert_eq!(Operators::EVERY.to_string(), "+-*/^||%");
+    let basic = Operators::from_iter([
+        Operator::Div,
+        Operator::Mul,
+        Operator::Sub,
+        Operator::Add,
+    ]);
+    assert_eq!(basic.to_string(), "+-*/");
+    let some = Operators::from_iter([Operator::Mul, Operator::Sub]);
+    assert_eq!(some.to_string(), "-*");
+    assert_eq!(Operators::NONE.to_string(), "");
+    #[cfg(feature = "parsing")]
+    assert_eq!(Operators::parse_line(&some.to_string()), Ok(some));
+}
+#[test]
+fn test_mod() {
+    use super::numbers::NormalNumberSystem;
+    assert_eq!(Operator::Mod.apply(&NormalNumberSystem, 17u32, 5), Some(2));
+    assert_eq!(Operator::Mod.apply(&NormalNumberSystem, 17u32, 0), None);
+    assert_eq!(Operator::Mod.to_string(), "%");
+    #[cfg(feature = "parsing")]
+    for line in ["%", "mod", "MOD"] {
+        assert_eq!(Operator::parse_line(line), Ok(Operator::Mod));
+    }
+}
+#[test]
+fn test_expression_order() {
+    let value = |t| Box::new(Expression::Value(t));
+    let app = |t, op, left: u32, right: u32| {
+        Expression::Application(t, op, value(left), value(right))
+    };
+    let canonical = vec![
+        Expression::Value(6u32),
+        app(6, Operator::Add, 4, 2),
+        app(6, Operator::Add, 5, 1),
+        app(6, Operator::Mul, 3, 2),
+        app(6, Operator::Div, 12, 2),
+        Expression::Application(
+            6,
+            Operator::Add,
+            value(1),
+            Box::new(app(5, Operator::Add, 3, 2)),
+        ),
+        app(8, Operator::Add, 5, 3),
+    ];
+    let mut shuffled = canonical.clone();
+    // A fixed permutation so the test is deterministic.
+    for (i, j) in [(0, 6), (1, 4), (2, 5), (3, 0), (5, 1)] {
+        shuffled.swap(i, j);
+    }
+    assert_ne!(shuffled, canonical);
+    shuffled.sort();
+    assert_eq!(shuffled, canonical);
+    assert_eq!(canonical[3].cmp(&canonical[3].clone()), Ordering::Equal);
+}
+#[test]
+fn test_score() {
+    let value = |t| Box::new(Expression::Value(t));
+    let div = Expression::Application(6u32, Operator::Div, value(12), value(2));
+    let mul = Expression::Application(6u32, Operator::Mul, value(3), value(2));
+    let weights = ScoreWeights::default().node_count(1);
+    assert_eq!(div.score(&weights), mul.score(&weights));
+    let weights = weights.operator(Operator::Div, 10);
+    assert_eq!(div.score(&weights), 13);
+    assert!(mul.score(&weights) < div.score(&weights));
+    let big = Expression::Application(
+        6u32,
+        Operator::Sub,
+        Box::new(Expression::Application(
+            106,
+            Operator::Add,
+            value(100),
+            value(6),
+        )),
+        value(100),
+    );
+    let weights = ScoreWeights::default().magnitude(5);
+    assert_eq!(big.score(&weights), 15);
+    assert_eq!(mul.score(&weights), 5);
+    assert_eq!(Expression::Value(1000u32).score(&weights), 0);
+    assert_eq!(digits(-120i32), 3);
+    assert_eq!(digits(i32::MIN), 10);
+    assert_eq!(digits(200u8), 3);
+}
+#[test]
+fn test_score_rational() {
+    use super::rational::Rational;
+    let value = |t| Box::new(Expression::Value(t));
+    let third = Expression::Application(
+        Rational::new(1u32, 3),
+        Operator::Div,
+        value(Rational::from(1)),
+        value(Rational::from(3)),
+    );
+    let weights = ScoreWeights::default().magnitude(5);
+    assert_eq!(third.score(&weights), 5);
+    let large = Expression::Application(
+        Rational::new(-2405i32, 2),
+        Operator::Div,
+        Box::new(Expression::Value(Rational::from(-2405))),
+        Box::new(Expression::Value(Rational::from(2))),
+    );
+    assert_eq!(large.score(&weights), 20);
+    assert_eq!(digits(Rational::new(250u8, 1)), 3);
+}
+#[test]
+fn test_canonicalize() {
+    let value = |t| Box::new(Expression::Value(t));
+    let sum = |left, right| {
+        Expression::Application(10u32, Operator::Add, value(left), value(right))
+    };
+    assert_ne!(sum(4, 6), sum(6, 4));
+    assert_eq!(sum(4, 6).canonicalize(), sum(6, 4).canonicalize());
+    assert_eq!(sum(4, 6).canonicalize(), sum(6, 4));
+    // Nested commutative operands are reordered, but not those of `-`.
+    let product = |left, right| {
+        Expression::Application(
+            30,
+            Operator::Mul,
+            Box::new(left),
+            Box::new(right),
+        )
+    };
+    assert_eq!(
+        product(*value(3), sum(4, 6)).canonicalize(),
+        product(sum(6, 4), *value(3))
+    );
+    let difference =
+        Expression::Application(2u32, Operator::Sub, value(6), value(4));
+    assert_eq!(difference.canonicalize(), difference);
+}
+#[test]
+fn test_is_legal() {
+    let value = |t| Box::new(Expression::Value(t));
+    let square =
+        Expression::Application(25u32, Operator::Mul, value(5), value(5));
+    assert!(square.is_legal(&BTreeMap::from([(5, 2), (3, 1)])));
+    assert!(!square.is_legal(&BTreeMap::from([(5, 1), (3, 1)])));
+    assert!(!square.is_legal(&BTreeMap::from([(3, 2)])));
+    assert!(!Expression::Value(7u32).is_legal(&BTreeMap::new()));
+    assert!(Expression::Value(7u32).is_legal(&BTreeMap::from([(7, 1)])));
+}
+#[test]
+fn test_steps() {
+    let value = |t| Box::new(Expression::Value(t));
+    let sum = Expression::Application(10u32, Operator::Add, value(6), value(4));
+    let product = Expression::Application(
+        30,
+        Operator::Mul,
+        Box::new(sum.clone()),
+        value(3),
+    );
+    let steps = product.steps();
+    assert_eq!(steps, [(sum, 10), (product.clone(), 30)]);
+    let described: Vec<String> = steps
+        .iter()
+        .map(|(expr, t)| {
+            match expr {
+                | Expression::Application(_, op, left, right) => {
+                    format!(
+                        "{} {op} {} = {t}",
+                        left.get_value(),
+                        right.get_value()
+                    )
+                }
+                | Expression::Value(_) => unreachable!(),
+            }
+        })
+        .collect();
+    assert_eq!(described, ["6 + 4 = 10", "10 * 3 = 30"]);
+    assert!(Expression::Value(3u32).steps().is_empty());
+}
+#[test]
+fn test_map_values() {
+    let value = |t| Box::new(Expression::Value(t));
+    let sum = Expression::Application(10u32, Operator::Add, value(6), value(4));
+    let product =
+        Expression::Application(30, Operator::Mul, Box::new(sum), value(3));
+    let widened = product.map_values(&|t| u64::from(*t));
+    assert_eq!(widened.to_string(), product.to_string());
+    assert_eq!(*widened.get_value(), 30u64);
+    assert_eq!(widened.node_count(), product.node_count());
+    assert_eq!(widened.leaves(), [6u64, 4, 3]);
+    assert!(widened.check(&super::numbers::NormalNumberSystem));
+}
+#[test]
+fn test_try_eval() {
+    use super::numbers::{NormalNumberSystem, SaturatingNumberSystem};
+    let value = |t| Box::new(Expression::Value(t));
+    // 200 + 100 only fits in a u8 when it saturates.
+    let sum =
+        Expression::Application(255u8, Operator::Add, value(200), value(100));
+    let expr = Expression::Application(
+        51,
+        Operator::Div,
+        Box::new(sum.clone()),
+        value(5),
+    );
+    assert_eq!(expr.try_eval(&SaturatingNumberSystem), Ok(51));
+    assert!(expr.check(&SaturatingNumberSystem));
+    assert_eq!(expr.try_eval(&NormalNumberSystem), Err(&sum));
+    assert!(!expr.check(&NormalNumberSystem));
+    let inexact =
+        Expression::Application(3u8, Operator::Div, value(7), value(2));
+    assert_eq!(inexact.try_eval(&NormalNumberSystem), Err(&inexact));
+}
+#[test]
+fn test_operators_from_chars() {
+    assert_eq!(
+        Operators::from_chars("+-"),
+        Ok(Operators::from_iter([Operator::Add, Operator::Sub]))
+    );
+    assert_eq!(Operators::from_chars(""), Ok(Operators::NONE));
+    assert_eq!(Operators::from_chars("+-*/"), Ok(Operators::ALL));
+    assert_eq!(Operators::from_chars("+-*/^||%"), Ok(Operators::EVERY));
+    assert_eq!(
+        Operators::from_chars(&Operators::EVERY.to_string()),
+        Ok(Operators::EVERY)
+    );
+    assert_eq!(
+        Operators::from_chars("+a"),
+        Err(String::from("Unknown operator 'a' in \"+a\""))
+    );
+    assert!(Operators::from_chars("|").is_err());
+    assert!(Operators::from_chars("+ -").is_err());
+}
+#[test]
+fn test_accessors() {
+    let leaf = Expression::Value(3u32);
+    assert_eq!(leaf.root_operator(), None);
+    assert_eq!(leaf.left(), None);
+    assert_eq!(leaf.right(), None);
+    let expr = Expression::Application(
+        7u32,
+        Operator::Add,
+        Box::new(Expression::Value(4)),
+        Box::new(leaf.clone()),
+    );
+    assert_eq!(expr.root_operator(), Some(Operator::Add));
+    assert_eq!(expr.left(), Some(&Expression::Value(4)));
+    assert_eq!(expr.right(), Some(&leaf));
+}
+#[test]
+fn test_no_division_number_system() {
+    use super::numbers::{NoDivisionNumberSystem, NormalNumberSystem};
+    let value = |t| Box::new(Expression::Value(t));
+    let system = NoDivisionNumberSystem::new(NormalNumberSystem);
+    let product =
+        Expression::Application(12u32, Operator::Mul, value(4), value(3));
+    assert_eq!(product.try_eval(&system), Ok(12));
+    let quotient = Expression::Application(
+        4,
+        Operator::Div,
+        Box::new(product.clone()),
+        value(3),
+    );
+    assert_eq!(quotient.try_eval(&NormalNumberSystem), Ok(4));
+    assert_eq!(quotient.try_eval(&system), Err(&quotient));
+}
+#[test]
+fn test_operator_from_u8() {
+    for op in Operators::ALL {
+        assert_eq!(Operator::from_u8(op as u8), Some(op));
+        assert_eq!(op.as_ref(), &Operators::from_iter([op]));
+        assert_eq!(op.as_ref().into_iter().collect::<Vec<_>>(), [op]);
+    }
+    assert_eq!(Operator::from_u8(0), None);
+    assert_eq!(Operator::from_u8(3), None);
+    assert_eq!(Operator::from_u8(128), None);
+}
+#[test]
+fn test_operators_set_algebra() {
+    let arithmetic = Operators::from_chars("+-*/").unwrap();
+    let additive = Operators::from_chars("+-").unwrap();
+    let extra = Operators::from_chars("^||").unwrap();
+    assert_eq!(additive | extra, Operators::from_chars("+-^||").unwrap());
+    assert_eq!(arithmetic & additive, additive);
+    assert_eq!(arithmetic & extra, Operators::NONE);
+    assert_eq!(!arithmetic, Operators::from_chars("^||%").unwrap());
+    assert_eq!(!Operators::EVERY, Operators::NONE);
+    assert_eq!(!!additive, additive);
+    assert_eq!(
+        Operators::from_iter([additive, extra, arithmetic]),
+        Operators::from_chars("+-*/^||").unwrap()
+    );
+    assert_eq!(
+        Operators::from_iter(Vec::<Operators>::new()),
+        !Operators::EVERY
+    );
+}
+#[test]
+fn test_max_intermediate() {
+    let value = |t| Box::new(Expression::Value(t));
+    let square = Expression::Application(
+        10000u32,
+        Operator::Mul,
+        value(100),
+        value(100),
+    );
+    let expr = Expression::Application(
+        9999,
+        Operator::Sub,
+        Box::new(square),
+        value(1),
+    );
+    assert_eq!(expr.max_intermediate(), 10000);
+    assert_eq!(Expression::Value(7u32).max_intermediate(), 7);
+    let signed = |t| Box::new(Expression::Value(t));
+    let negative =
+        Expression::Application(-8i32, Operator::Sub, signed(2), signed(10));
+    assert_eq!(negative.max_intermediate(), 10);
+    let negative = Expression::Application(
+        -5i32,
+        Operator::Sub,
+        Box::new(negative),
+        signed(-3),
+    );
+    assert_eq!(negative.max_intermediate(), 10);
+    assert_eq!(Expression::Value(i8::MIN).max_intermediate(), i8::MAX);
+}
@@ -0,0 +1,232 @@
+//! # Floating Point Numbers
+//! A totally ordered wrapper around `f64` so the countdown problem can be
+//! solved over the reals.
+
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    iter::Sum,
+    ops::{
+        Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, Sub, SubAssign,
+    },
+};
+
+use super::numbers::{CheckedOperations, NumberSystem, NumberType};
+
+/// A float which is never NaN, ordered by [`f64::total_cmp`]. Negative zero
+/// is stored as zero so that the two compare equal.
+#[derive(Clone, Copy, Debug)]
+pub struct OrderedFloat(f64);
+
+impl OrderedFloat {
+    /// Wraps the float, returning `None` if it is NaN.
+    pub fn new(value: f64) -> Option<Self> {
+        if value.is_nan() {
+            None
+        } else if value == 0.0 {
+            Some(Self(0.0))
+        } else {
+            Some(Self(value))
+        }
+    }
+    /// Wraps the float, returning `None` if it is NaN or infinite.
+    pub fn finite(value: f64) -> Option<Self> {
+        value.is_finite().then(|| Self::new(value)).flatten()
+    }
+    /// Gets the wrapped float
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+impl Display for OrderedFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for OrderedFloat {}
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+macro_rules! impl_operator {
+    ($trait:ident, $f:ident, $assign_trait:ident, $assign:ident) => {
+        impl $trait for OrderedFloat {
+            type Output = Self;
+
+            fn $f(self, rhs: Self) -> Self {
+                Self::new($trait::$f(self.0, rhs.0)).expect(concat!(
+                    "attempt to ",
+                    stringify!($f),
+                    " to NaN"
+                ))
+            }
+        }
+        impl $assign_trait for OrderedFloat {
+            fn $assign(&mut self, rhs: Self) {
+                *self = $trait::$f(*self, rhs);
+            }
+        }
+    };
+}
+impl_operator!(Add, add, AddAssign, add_assign);
+impl_operator!(Sub, sub, SubAssign, sub_assign);
+impl_operator!(Mul, mul, MulAssign, mul_assign);
+impl_operator!(Div, div, DivAssign, div_assign);
+impl Rem for OrderedFloat {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        Self::new(self.0 % rhs.0).expect("attempt to rem to NaN")
+    }
+}
+impl Sum for OrderedFloat {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+/// The checked operations fail whenever the result is not finite.
+impl CheckedOperations for OrderedFloat {
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::finite(self.0 + rhs.0)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::finite(self.0 - rhs.0)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Self::finite(self.0 * rhs.0)
+    }
+
+    fn checked_div(self, rhs: Self) -> Option<Self> {
+        Self::finite(self.0 / rhs.0)
+    }
+
+    fn checked_rem(self, rhs: Self) -> Option<Self> {
+        Self::finite(self.0 % rhs.0)
+    }
+
+    fn saturating_add(self, rhs: Self) -> Option<Self> {
+        Self::new((self.0 + rhs.0).clamp(f64::MIN, f64::MAX))
+    }
+
+    fn saturating_sub(self, rhs: Self) -> Option<Self> {
+        Self::new((self.0 - rhs.0).clamp(f64::MIN, f64::MAX))
+    }
+
+    fn saturating_mul(self, rhs: Self) -> Option<Self> {
+        Self::new((self.0 * rhs.0).clamp(f64::MIN, f64::MAX))
+    }
+}
+impl NumberType for OrderedFloat {
+    const ONE: Self = Self(1.0);
+    const ZERO: Self = Self(0.0);
+}
+
+/// A number system over finite floats, where division only fails for a zero
+/// divisor.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FloatNumberSystem;
+impl NumberSystem<OrderedFloat> for FloatNumberSystem {
+    fn add(
+        &self,
+        one: OrderedFloat,
+        other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        one.checked_add(other)
+    }
+
+    fn sub(
+        &self,
+        one: OrderedFloat,
+        other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        one.checked_sub(other)
+    }
+
+    fn mul(
+        &self,
+        one: OrderedFloat,
+        other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        one.checked_mul(other)
+    }
+
+    fn div(
+        &self,
+        one: OrderedFloat,
+        other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        (other != OrderedFloat::ZERO)
+            .then(|| one.checked_div(other))
+            .flatten()
+    }
+
+    fn pow(
+        &self,
+        one: OrderedFloat,
+        other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        OrderedFloat::finite(one.0.powf(other.0))
+    }
+
+    fn concat(
+        &self,
+        _one: OrderedFloat,
+        _other: OrderedFloat,
+    ) -> Option<OrderedFloat> {
+        None
+    }
+}
+
+#[test]
+fn test_float_number_system() {
+    let float = |f| OrderedFloat::new(f).unwrap();
+    assert_eq!(
+        FloatNumberSystem.div(float(10.0), float(4.0)),
+        Some(float(2.5))
+    );
+    assert_eq!(FloatNumberSystem.div(float(10.0), float(0.0)), None);
+    assert_eq!(FloatNumberSystem.mul(float(f64::MAX), float(2.0)), None);
+    assert_eq!(
+        float(f64::MAX).saturating_mul(float(2.0)),
+        Some(float(f64::MAX))
+    );
+    assert_eq!(OrderedFloat::new(f64::NAN), None);
+    assert_eq!(float(-0.0), float(0.0));
+    assert!(float(-1.5) < float(0.5));
+}
+#[test]
+fn test_find_float_expressions() {
+    use super::expressions::Operators;
+    use crate::{
+        generators::expression_tree_generator::{
+            SearchOptions, find_expressions,
+        },
+        timing::caching::CachingTransciever,
+    };
+    let float = |f| OrderedFloat::new(f).unwrap();
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![float(10.0), float(4.0)],
+        &FloatNumberSystem,
+        float(2.5),
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> =
+        results.as_ref().iter().map(|a| a.to_string()).collect();
+    assert_eq!(found, ["10 / 4"]);
+}
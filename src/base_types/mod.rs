@@ -1,2 +1,6 @@
 pub mod expressions;
+#[cfg(feature = "std")]
+pub mod float;
 pub mod numbers;
+pub mod rational;
+pub mod rc_expression;
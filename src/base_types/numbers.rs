@@ -2,14 +2,18 @@
 //! A `NumberType` is any type which can be used for the countdown problem. This
 //! requires Addition, Subtraction, Multiplication, Division.
 
-use std::{
+use alloc::{format, string::String};
+use core::{
     fmt::{Debug, Display},
     iter::Sum,
     ops::{
         Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, Sub, SubAssign,
     },
 };
-/// A helper trait which defines the required methods for a number.
+
+use super::expressions::Operator;
+/// A helper trait which defines the required methods for a number. Numbers
+/// are `Send` and `Sync` so a search can be spread over threads.
 pub trait NumberType:
     Sized
     + Copy
@@ -29,6 +33,8 @@ pub trait NumberType:
     + Eq
     + 'static
     + Ord
+    + Send
+    + Sync
 {
     /// Number representing one
     const ONE: Self;
@@ -37,85 +43,407 @@ pub trait NumberType:
     /// Checks whether a number is prime
     fn is_prime(self) -> bool {
         let mut a = Self::ONE + Self::ONE;
-        while (a * a) <= self {
-            if a % self == Self::ZERO {
+        if self < a {
+            return false;
+        }
+        // Dividing rather than squaring avoids overflow near the maximum.
+        while a <= self / a {
+            if self % a == Self::ZERO {
                 return false;
             }
             a += Self::ONE;
         }
         true
     }
-}
-struct CountdownRange<T: NumberType> {
-    start: T,
-    end: T,
-    inclusive: bool,
-}
-impl<T: NumberType> Iterator for CountdownRange<T> {
-    type Item = T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.start < self.end || (self.inclusive && (self.start == self.end))
-        {
-            let result = Some(self.start);
-            self.start += T::ONE;
-
-            result
+    /// The greatest common divisor by the Euclidean algorithm, which is never
+    /// negative. The gcd of zero and `n` is `n`.
+    fn gcd(self, other: Self) -> Self {
+        let abs = |t: Self| if t < Self::ZERO { Self::ZERO - t } else { t };
+        let (mut a, mut b) = (abs(self), abs(other));
+        while b != Self::ZERO {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+    /// The least common multiple, which is never negative. It is zero if
+    /// either number is zero.
+    fn lcm(self, other: Self) -> Self {
+        if self == Self::ZERO || other == Self::ZERO {
+            return Self::ZERO;
+        }
+        let multiple = self / self.gcd(other) * other;
+        if multiple < Self::ZERO {
+            Self::ZERO - multiple
         } else {
-            None
+            multiple
         }
     }
 }
 /// A trait which represents a number system. The four basic operations are
-/// defined.
-pub trait NumberSystem<T: NumberType>: Clone + Copy + Debug {
+/// defined. Like numbers, a number system can be shared between threads.
+pub trait NumberSystem<T: NumberType>:
+    Clone + Copy + Debug + Send + Sync
+{
     fn add(&self, one: T, other: T) -> Option<T>;
     fn sub(&self, one: T, other: T) -> Option<T>;
     fn mul(&self, one: T, other: T) -> Option<T>;
     fn div(&self, one: T, other: T) -> Option<T>;
+    /// Raises `one` to the power of `other`. By default this is computed by
+    /// repeated squaring.
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        checked_pow(one, other)
+    }
+    /// Concatenates the decimal digits of `one` and `other`, so `2` and `5`
+    /// become `25`.
+    fn concat(&self, one: T, other: T) -> Option<T> {
+        checked_concat(one, other)
+    }
+    /// The remainder of dividing `one` by `other`, which is rejected for a
+    /// zero divisor.
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        (other != T::ZERO).then(|| one.checked_rem(other)).flatten()
+    }
+    /// Checks that `t` is a value of this number system, describing why it
+    /// is not otherwise. By default every value of the type is accepted.
+    fn check_value(&self, _t: T) -> Result<(), String> {
+        Ok(())
+    }
+    /// Brings a source number into the values of this number system before
+    /// it is used as an operand. By default every value is left unchanged.
+    fn reduce(&self, t: T) -> T {
+        t
+    }
+    /// Whether expressions which evaluate to zero should be kept by the
+    /// generator. By default they are dropped.
+    fn allows_zero_results(&self) -> bool {
+        false
+    }
+    /// Whether the generator keeps an expression which evaluates to `t`,
+    /// which is any value but zero unless
+    /// [`allows_zero_results`](Self::allows_zero_results) says otherwise.
+    fn keeps_result(&self, t: T) -> bool {
+        t != T::ZERO || self.allows_zero_results()
+    }
+    /// The value `e` with `x op e == x` for every `x`, if there is one. By
+    /// default this is zero for addition and subtraction, and one for
+    /// multiplication, division and powers. No divisor leaves every
+    /// remainder unchanged.
+    fn identity(&self, op: Operator) -> Option<T> {
+        match op {
+            | Operator::Add | Operator::Sub => Some(T::ZERO),
+            | Operator::Mul | Operator::Div | Operator::Pow => Some(T::ONE),
+            | Operator::Concat | Operator::Mod => None,
+        }
+    }
 }
-/// A number system which represents normal arithmetic
-#[derive(Clone, Copy, Debug)]
+/// Computes `one` to the power of `other` by repeated checked squaring,
+/// returning `None` on overflow or a negative exponent.
+pub(crate) fn checked_pow<T: NumberType>(one: T, mut other: T) -> Option<T> {
+    if other < T::ZERO {
+        return None;
+    }
+    let two = T::ONE + T::ONE;
+    let (mut base, mut result) = (one, T::ONE);
+    while other > T::ZERO {
+        if other % two == T::ONE {
+            result = result.checked_mul(base)?;
+        }
+        other /= two;
+        // The square is only needed for a higher bit, in which case the
+        // result is at least as large, so this never overflows early.
+        if other > T::ZERO {
+            base = base.checked_mul(base)?;
+        }
+    }
+    Some(result)
+}
+/// Computes `one * 10^digits(other) + other` with checked arithmetic,
+/// returning `None` on overflow or a negative operand.
+pub(crate) fn checked_concat<T: NumberType>(one: T, other: T) -> Option<T> {
+    if one < T::ZERO || other < T::ZERO {
+        return None;
+    }
+    let ten = [T::ONE; 10].into_iter().sum::<T>();
+    let mut scale = ten;
+    let mut rest = other / ten;
+    while rest != T::ZERO {
+        scale = scale.checked_mul(ten)?;
+        rest /= ten;
+    }
+    one.checked_mul(scale)?.checked_add(other)
+}
+/// A number system which represents normal arithmetic, using the strict
+/// defaults of [`NormalNumberSystemConfig`].
+#[derive(Clone, Copy, Debug, Default)]
 pub struct NormalNumberSystem;
+impl NormalNumberSystem {
+    /// Creates a configurable normal number system, which starts out with the
+    /// same strict behaviour as [`NormalNumberSystem`].
+    pub fn builder() -> NormalNumberSystemConfig {
+        NormalNumberSystemConfig::default()
+    }
+}
 impl<T: NumberType> NumberSystem<T> for NormalNumberSystem {
     fn add(&self, one: T, other: T) -> Option<T> {
-        (one > other && one != T::ZERO && other != T::ZERO)
+        NormalNumberSystemConfig::default().add(one, other)
+    }
+
+    fn sub(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().sub(one, other)
+    }
+
+    fn mul(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().mul(one, other)
+    }
+
+    fn div(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().div(one, other)
+    }
+
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().pow(one, other)
+    }
+
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().rem(one, other)
+    }
+}
+/// Normal arithmetic with configurable rules for which operands are
+/// accepted. By default zero operands, identity operands, commutative
+/// duplicates and negative results are all rejected to reduce the search
+/// space.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalNumberSystemConfig {
+    allow_zero_operands: bool,
+    allow_identity_operands: bool,
+    allow_commutative_duplicates: bool,
+    allow_negative_results: bool,
+}
+impl NormalNumberSystemConfig {
+    /// Allows `0` as an operand of addition, subtraction and powers.
+    pub fn allow_zero_operands(mut self, allow: bool) -> Self {
+        self.allow_zero_operands = allow;
+        self
+    }
+    /// Allows `1` as an operand of multiplication, division and powers.
+    pub fn allow_identity_operands(mut self, allow: bool) -> Self {
+        self.allow_identity_operands = allow;
+        self
+    }
+    /// Allows both `a + b` and `b + a` (and likewise for multiplication)
+    /// instead of only the one with the larger operand first.
+    pub fn allow_commutative_duplicates(mut self, allow: bool) -> Self {
+        self.allow_commutative_duplicates = allow;
+        self
+    }
+    /// Allows `a - b` when `b` is larger, producing a negative value for
+    /// signed types. Negative values are then used as operands like any
+    /// other; only zero results are still dropped by the generator. Unsigned
+    /// types simply fail the checked subtraction.
+    pub fn allow_negative_results(mut self, allow: bool) -> Self {
+        self.allow_negative_results = allow;
+        self
+    }
+    fn non_zero<T: NumberType>(&self, one: T, other: T) -> bool {
+        self.allow_zero_operands || (one != T::ZERO && other != T::ZERO)
+    }
+    fn non_identity<T: NumberType>(&self, one: T, other: T) -> bool {
+        self.allow_identity_operands || (one != T::ONE && other != T::ONE)
+    }
+    fn ordered<T: NumberType>(&self, one: T, other: T) -> bool {
+        self.allow_commutative_duplicates || one > other
+    }
+    /// Whether the rules accept `one` and `other` as the operands of `op`,
+    /// before the operation itself is tried.
+    fn accepts<T: NumberType>(&self, op: Operator, one: T, other: T) -> bool {
+        match op {
+            | Operator::Add => {
+                self.ordered(one, other) && self.non_zero(one, other)
+            }
+            | Operator::Sub => {
+                (self.allow_negative_results || one > other)
+                    && self.non_zero(one, other)
+            }
+            | Operator::Mul => {
+                self.ordered(one, other) && self.non_identity(one, other)
+            }
+            | Operator::Div => one > other && self.non_identity(one, other),
+            | Operator::Pow => {
+                self.non_zero(one, other) && self.non_identity(one, other)
+            }
+            | Operator::Mod => one > other,
+            | Operator::Concat => true,
+        }
+    }
+}
+impl<T: NumberType> NumberSystem<T> for NormalNumberSystemConfig {
+    fn add(&self, one: T, other: T) -> Option<T> {
+        self.accepts(Operator::Add, one, other)
             .then(|| one.checked_add(other))
             .flatten()
     }
 
     fn sub(&self, one: T, other: T) -> Option<T> {
-        (one > other && one != T::ZERO && other != T::ZERO)
+        self.accepts(Operator::Sub, one, other)
             .then(|| one.checked_sub(other))
             .flatten()
     }
 
     fn mul(&self, one: T, other: T) -> Option<T> {
-        (one > other && one != T::ONE && other != T::ONE)
+        self.accepts(Operator::Mul, one, other)
             .then(|| one.checked_mul(other))
             .flatten()
     }
 
     fn div(&self, one: T, other: T) -> Option<T> {
-        (one > other && one != T::ONE && other != T::ONE)
+        (self.accepts(Operator::Div, one, other)
+            && other != T::ZERO
+            && one % other == T::ZERO)
             .then(|| one.checked_div(other))
             .flatten()
     }
+
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        self.accepts(Operator::Pow, one, other)
+            .then(|| checked_pow(one, other))
+            .flatten()
+    }
+
+    /// Only a divisor smaller than `one` is accepted, since any other leaves
+    /// `one` unchanged.
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        (self.accepts(Operator::Mod, one, other) && other != T::ZERO)
+            .then(|| one.checked_rem(other))
+            .flatten()
+    }
+}
+/// Arithmetic where addition, subtraction and multiplication clamp at the
+/// bounds of the type instead of rejecting overflow, so `200u8 + 100` is
+/// `255`. Results which saturated are no longer exact: an expression's value
+/// is only what the clamped steps give, not its true arithmetic value.
+/// Operands are accepted by the same rules as [`NormalNumberSystem`], and
+/// division, powers and remainders are computed just as it does them, so
+/// they still reject overflow.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SaturatingNumberSystem;
+impl<T: NumberType> NumberSystem<T> for SaturatingNumberSystem {
+    fn add(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default()
+            .accepts(Operator::Add, one, other)
+            .then(|| one.saturating_add(other))
+            .flatten()
+    }
+
+    fn sub(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default()
+            .accepts(Operator::Sub, one, other)
+            .then(|| one.saturating_sub(other))
+            .flatten()
+    }
+
+    fn mul(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default()
+            .accepts(Operator::Mul, one, other)
+            .then(|| one.saturating_mul(other))
+            .flatten()
+    }
+
+    fn div(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().div(one, other)
+    }
+
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().pow(one, other)
+    }
+
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        NormalNumberSystemConfig::default().rem(one, other)
+    }
+}
+/// Wraps another number system, delegating every operation to it except
+/// division, which is always rejected. Expressions can be checked against "no
+/// division" rules with [`try_eval`](super::expressions::Expression::try_eval)
+/// under this system, whatever the operators used to find them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoDivisionNumberSystem<N>(N);
+impl<N> NoDivisionNumberSystem<N> {
+    /// Rejects division on top of the given system
+    pub fn new(inner: N) -> Self {
+        Self(inner)
+    }
+    /// Gets the wrapped system
+    pub fn inner(&self) -> &N {
+        &self.0
+    }
+}
+impl<T: NumberType, N: NumberSystem<T>> NumberSystem<T>
+    for NoDivisionNumberSystem<N>
+{
+    fn add(&self, one: T, other: T) -> Option<T> {
+        self.0.add(one, other)
+    }
+
+    fn sub(&self, one: T, other: T) -> Option<T> {
+        self.0.sub(one, other)
+    }
+
+    fn mul(&self, one: T, other: T) -> Option<T> {
+        self.0.mul(one, other)
+    }
+
+    fn div(&self, _one: T, _other: T) -> Option<T> {
+        None
+    }
+
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        self.0.pow(one, other)
+    }
+
+    fn concat(&self, one: T, other: T) -> Option<T> {
+        self.0.concat(one, other)
+    }
+
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        self.0.rem(one, other)
+    }
+
+    fn check_value(&self, t: T) -> Result<(), String> {
+        self.0.check_value(t)
+    }
+
+    fn reduce(&self, t: T) -> T {
+        self.0.reduce(t)
+    }
+
+    fn allows_zero_results(&self) -> bool {
+        self.0.allows_zero_results()
+    }
+
+    fn identity(&self, op: Operator) -> Option<T> {
+        self.0.identity(op)
+    }
 }
-/// A number system which implements modular arithmetic
+/// A number system which implements modular arithmetic. Every result is
+/// reduced into `[0, modulus)`, and every operand must already be in that
+/// range, which [`reduce`](NumberSystem::reduce) ensures for source numbers.
 #[derive(Clone, Copy, Debug)]
-pub struct ModularNumberSystem<T: NumberType>(T, bool);
+pub struct ModularNumberSystem<T: NumberType>(T, bool, bool);
 
 impl<T: NumberType> ModularNumberSystem<T> {
     /// Creating a modular number system from a value.
     pub fn new(base: T) -> Self {
-        Self(base, base.is_prime())
+        Self(base, base.is_prime(), false)
+    }
+    /// Sets whether expressions which evaluate to zero are kept. Zero is a
+    /// perfectly good residue, but it is dropped by default to keep the
+    /// search space small.
+    pub fn with_zero_results(mut self, allow: bool) -> Self {
+        self.2 = allow;
+        self
     }
     fn in_range(&self, t: &mut T) {
-        while *t >= self.0 {
-            *t -= self.0;
-        }
-        while *t < T::ZERO {
+        *t = *t % self.0;
+        if *t < T::ZERO {
             *t += self.0;
         }
         debug_assert!(*t >= T::ZERO && *t < self.0)
@@ -124,19 +452,68 @@ impl<T: NumberType> ModularNumberSystem<T> {
         self.in_range(&mut t);
         t
     }
-    fn pow(&self, t: T, n: T) -> T {
-        let mut v = T::ONE;
-        for _ in (CountdownRange {
-            start: T::ZERO,
-            end: n,
-            inclusive: false,
-        }) {
-            v = self.t_into_range(v * t);
+    /// Raises `t` to the power `n` by repeated squaring, reducing after
+    /// every multiplication. Returns `None` if a product overflows before it
+    /// can be reduced.
+    fn pow(&self, t: T, mut n: T) -> Option<T> {
+        let two = T::ONE + T::ONE;
+        let (mut base, mut v) = (t, T::ONE);
+        while n > T::ZERO {
+            if n % two == T::ONE {
+                v = NumberSystem::mul(self, v, base)?;
+            }
+            n /= two;
+            if n > T::ZERO {
+                base = NumberSystem::mul(self, base, base)?;
+            }
+        }
+        Some(v)
+    }
+    /// Computes `one - other` for two values which are in range.
+    fn sub_in_range(&self, one: T, other: T) -> T {
+        if one >= other {
+            one - other
+        } else {
+            one + (self.0 - other)
+        }
+    }
+    /// Computes `one + other` for two values which are in range, without
+    /// ever going above the modulus.
+    fn add_in_range(&self, one: T, other: T) -> T {
+        match other == T::ZERO {
+            | true => one,
+            | false => self.sub_in_range(one, self.0 - other),
         }
-        v
     }
-    fn multiplicative_inverse(&self, t: T) -> T {
-        self.pow(t, self.0 - T::ONE)
+    /// Computes `one * other` for two values which are in range by doubling
+    /// and adding, so it can not overflow even when the product of the two
+    /// would.
+    fn mul_in_range(&self, mut one: T, mut other: T) -> T {
+        let two = T::ONE + T::ONE;
+        let mut product = T::ZERO;
+        while other > T::ZERO {
+            if other % two == T::ONE {
+                product = self.add_in_range(product, one);
+            }
+            one = self.add_in_range(one, one);
+            other /= two;
+        }
+        product
+    }
+    /// Finds the multiplicative inverse using the extended Euclidean
+    /// algorithm. The Bezout coefficient is kept in `[0, modulus)` at every
+    /// step, so it never goes negative even for signed types. Returns `None`
+    /// if `t` has no inverse.
+    fn multiplicative_inverse(&self, t: T) -> Option<T> {
+        let (mut old_r, mut r) = (self.0, t);
+        let (mut old_s, mut s) = (T::ZERO, T::ONE);
+        while r != T::ZERO {
+            let quotient = old_r / r;
+            (old_r, r) = (r, old_r - quotient * r);
+            let product = self.mul_in_range(self.t_into_range(quotient), s);
+            (old_s, s) = (s, self.sub_in_range(old_s, product));
+        }
+        (old_r == T::ONE).then_some(old_s)
     }
 }
 
@@ -150,11 +527,7 @@ impl<T: NumberType> NumberSystem<T> for ModularNumberSystem<T> {
     fn sub(&self, one: T, other: T) -> Option<T> {
         debug_assert!(one < self.0 && one >= T::ZERO);
         debug_assert!(other < self.0 && other >= T::ZERO);
-        if one != other {
-            self.add(one, self.0.checked_sub(other)?)
-        } else {
-            None
-        }
+        Some(self.sub_in_range(one, other))
     }
 
     fn mul(&self, one: T, other: T) -> Option<T> {
@@ -169,7 +542,61 @@ impl<T: NumberType> NumberSystem<T> for ModularNumberSystem<T> {
         if !self.1 {
             return None;
         }
-        self.mul(one, self.multiplicative_inverse(other))
+        self.mul(one, self.multiplicative_inverse(other)?)
+    }
+
+    fn pow(&self, one: T, other: T) -> Option<T> {
+        debug_assert!(one < self.0 && one >= T::ZERO);
+        debug_assert!(other < self.0 && other >= T::ZERO);
+        ModularNumberSystem::pow(self, one, other)
+    }
+
+    /// Concatenates the digits of the two residues, reducing the result.
+    fn concat(&self, one: T, other: T) -> Option<T> {
+        debug_assert!(one < self.0 && one >= T::ZERO);
+        debug_assert!(other < self.0 && other >= T::ZERO);
+        Some(self.t_into_range(checked_concat(one, other)?))
+    }
+
+    fn rem(&self, one: T, other: T) -> Option<T> {
+        debug_assert!(one < self.0 && one >= T::ZERO);
+        debug_assert!(other < self.0 && other >= T::ZERO);
+        (other != T::ZERO).then(|| one.checked_rem(other)).flatten()
+    }
+
+    fn allows_zero_results(&self) -> bool {
+        self.2
+    }
+
+    /// Source numbers are reduced to their residue.
+    fn reduce(&self, t: T) -> T {
+        self.t_into_range(t)
+    }
+
+    /// Only the residues `0` to `modulus - 1` are values.
+    fn check_value(&self, t: T) -> Result<(), String> {
+        match t >= T::ZERO && t < self.0 {
+            | true => Ok(()),
+            | false => {
+                Err(format!(
+                    "{t} is not a residue modulo {}, which must be from 0 to {}",
+                    self.0,
+                    self.0 - T::ONE
+                ))
+            }
+        }
+    }
+
+    /// The usual identities reduced into range, so the modulus `1` has `0`
+    /// as its multiplicative identity. Division only has an identity when
+    /// the modulus is prime.
+    fn identity(&self, op: Operator) -> Option<T> {
+        match op {
+            | Operator::Add | Operator::Sub => Some(T::ZERO),
+            | Operator::Mul | Operator::Pow => Some(self.t_into_range(T::ONE)),
+            | Operator::Div => self.1.then(|| self.t_into_range(T::ONE)),
+            | Operator::Concat | Operator::Mod => None,
+        }
     }
 }
 /// Checked operations to check for overflow.
@@ -178,6 +605,29 @@ pub trait CheckedOperations: Sized {
     fn checked_sub(self, rhs: Self) -> Option<Self>;
     fn checked_mul(self, rhs: Self) -> Option<Self>;
     fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    /// Adds, clamping at the bounds of the type. Types without fixed bounds
+    /// keep rejecting overflow, which is the default.
+    fn saturating_add(self, rhs: Self) -> Option<Self> {
+        self.checked_add(rhs)
+    }
+    /// Subtracts, clamping at the bounds of the type like
+    /// [`saturating_add`](Self::saturating_add).
+    fn saturating_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_sub(rhs)
+    }
+    /// Multiplies, clamping at the bounds of the type like
+    /// [`saturating_add`](Self::saturating_add).
+    fn saturating_mul(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs)
+    }
+}
+macro_rules! to_saturating_function {
+    ($f:ident) => {
+        fn $f(self, rhs: Self) -> Option<Self> {
+            Some(Self::$f(self, rhs))
+        }
+    };
 }
 macro_rules! to_function {
     ($f:ident) => {
@@ -193,6 +643,10 @@ macro_rules! impl_checked_operations {
             to_function!(checked_sub);
             to_function!(checked_mul);
             to_function!(checked_div);
+            to_function!(checked_rem);
+            to_saturating_function!(saturating_add);
+            to_saturating_function!(saturating_sub);
+            to_saturating_function!(saturating_mul);
         }
     };
 }
@@ -229,3 +683,208 @@ impl_countdown_number_type!(i32);
 impl_countdown_number_type!(i64);
 impl_countdown_number_type!(i128);
 impl_countdown_number_type!(isize);
+
+#[test]
+fn test_normal_division_is_exact() {
+    assert_eq!(NormalNumberSystem.div(7u32, 2), None);
+    assert_eq!(NormalNumberSystem.div(6u32, 2), Some(3));
+    assert_eq!(NormalNumberSystem.div(6u32, 0), None);
+}
+#[test]
+fn test_configurable_normal_number_system() {
+    let strict = NormalNumberSystem::builder();
+    assert_eq!(strict.add(5u32, 0), None);
+    assert_eq!(strict.mul(5u32, 1), None);
+    assert_eq!(strict.add(3u32, 5), None);
+    let relaxed = NormalNumberSystem::builder()
+        .allow_zero_operands(true)
+        .allow_identity_operands(true)
+        .allow_commutative_duplicates(true);
+    assert_eq!(relaxed.add(5u32, 0), Some(5));
+    assert_eq!(relaxed.mul(5u32, 1), Some(5));
+    assert_eq!(relaxed.add(3u32, 5), Some(8));
+    assert_eq!(relaxed.mul(3u32, 3), Some(9));
+    assert_eq!(relaxed.div(5u32, 1), Some(5));
+    assert_eq!(relaxed.sub(3u32, 5), None);
+    assert_eq!(relaxed.div(7u32, 0), None);
+}
+#[test]
+fn test_is_prime() {
+    const PRIMES: [u32; 15] =
+        [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+    for i in 0u32..50 {
+        assert_eq!(i.is_prime(), PRIMES.contains(&i), "{i}");
+    }
+    assert!(!(-7i32).is_prime());
+}
+#[test]
+fn test_modular_division_requires_prime() {
+    for modulus in [4u32, 6, 9, 15] {
+        assert!(!ModularNumberSystem::new(modulus).1);
+        assert_eq!(ModularNumberSystem::new(modulus).div(2, 3), None);
+    }
+    assert!(ModularNumberSystem::new(7u32).1);
+}
+#[test]
+fn test_multiplicative_inverse() {
+    for modulus in [2u32, 3, 5, 7, 11, 13, 101] {
+        let system = ModularNumberSystem::new(modulus);
+        assert_eq!(system.multiplicative_inverse(0), None);
+        for t in 1..modulus {
+            let inverse = system.multiplicative_inverse(t).unwrap();
+            assert_eq!(
+                Some(inverse),
+                system.pow(t, modulus - 2),
+                "{t} mod {modulus}"
+            );
+            assert_eq!(system.mul(t, inverse), Some(1));
+        }
+    }
+    let system = ModularNumberSystem::new(13i32);
+    for t in 1..13 {
+        assert_eq!(system.multiplicative_inverse(t), system.pow(t, 11));
+    }
+    assert_eq!(
+        ModularNumberSystem::new(9u32).multiplicative_inverse(3),
+        None
+    );
+    // The largest prime below `u32::MAX`, where the coefficients' products
+    // overflow the type.
+    let modulus = 4_294_967_291u32;
+    let system = ModularNumberSystem::new(modulus);
+    for t in [2, 3, 1_234_567, modulus - 1] {
+        let inverse = system.multiplicative_inverse(t).unwrap();
+        assert_eq!(u64::from(t) * u64::from(inverse) % u64::from(modulus), 1);
+    }
+}
+#[test]
+fn test_modular_sub_to_zero() {
+    let system = ModularNumberSystem::new(7u32);
+    assert_eq!(system.sub(3, 3), Some(0));
+    assert_eq!(system.sub(2, 5), Some(4));
+    assert!(!system.allows_zero_results());
+    assert!(system.with_zero_results(true).allows_zero_results());
+}
+#[test]
+fn test_checked_pow() {
+    assert_eq!(checked_pow(-1i32, 1_000_000_001), Some(-1));
+    assert_eq!(checked_pow(-1i64, i64::MAX - 1), Some(1));
+    assert_eq!(checked_pow(1u64, u64::MAX), Some(1));
+    assert_eq!(checked_pow(0u64, u64::MAX), Some(0));
+    assert_eq!(checked_pow(0u32, 0), Some(1));
+    assert_eq!(checked_pow(3u32, 5), Some(243));
+    assert_eq!(checked_pow(2u8, 7), Some(128));
+    assert_eq!(checked_pow(2u8, 8), None);
+    assert_eq!(checked_pow(-2i8, 7), Some(-128));
+    assert_eq!(checked_pow(2i8, 7), None);
+    assert_eq!(checked_pow(2u32, u32::MAX), None);
+    assert_eq!(checked_pow(2i32, -1), None);
+}
+#[test]
+fn test_modular_pow() {
+    let system = ModularNumberSystem::new(7u32);
+    assert_eq!(NumberSystem::pow(&system, 3, 4), Some(4));
+    // The largest 32 bit prime, where squaring most elements overflows.
+    let system = ModularNumberSystem::new(4_294_967_291u32);
+    assert_eq!(NumberSystem::pow(&system, 65_536, 2), None);
+    assert_eq!(NumberSystem::pow(&system, 2, 31), Some(1 << 31));
+    assert_eq!(NumberSystem::pow(&system, 70_000, 1), Some(70_000));
+    let system = ModularNumberSystem::new(4_294_967_291u64);
+    assert_eq!(NumberSystem::pow(&system, 65_536, 2), Some(5));
+    assert_eq!(NumberSystem::pow(&system, 2, 4_294_967_290), Some(1));
+}
+#[test]
+fn test_negative_results() {
+    let system = NormalNumberSystem::builder();
+    assert_eq!(system.sub(3i32, 5), None);
+    let system = system.allow_negative_results(true);
+    assert_eq!(system.sub(3i32, 5), Some(-2));
+    assert_eq!(system.sub(3u32, 5), None);
+    // Commutative operators are still deduplicated.
+    assert_eq!(system.add(-2i32, 5), None);
+    assert_eq!(system.add(5i32, -2), Some(3));
+}
+#[test]
+fn test_gcd_lcm() {
+    assert_eq!(12u32.gcd(18), 6);
+    assert_eq!(0u32.gcd(5), 5);
+    assert_eq!(5u32.gcd(0), 5);
+    assert_eq!(0u32.gcd(0), 0);
+    assert_eq!((-12i32).gcd(18), 6);
+    assert_eq!(12i64.gcd(-18), 6);
+    assert_eq!(4u32.lcm(6), 12);
+    assert_eq!((-4i32).lcm(6), 12);
+    assert_eq!(0u8.lcm(6), 0);
+}
+#[test]
+fn test_identity() {
+    let system = NormalNumberSystem;
+    let expected = [
+        (Operator::Add, Some(0)),
+        (Operator::Sub, Some(0)),
+        (Operator::Mul, Some(1)),
+        (Operator::Div, Some(1)),
+        (Operator::Pow, Some(1)),
+        (Operator::Concat, None),
+        (Operator::Mod, None),
+    ];
+    for (op, identity) in expected {
+        assert_eq!(NumberSystem::<u32>::identity(&system, op), identity);
+    }
+    let system = ModularNumberSystem::new(1u32);
+    assert_eq!(system.identity(Operator::Mul), Some(0));
+    assert_eq!(ModularNumberSystem::new(8u32).identity(Operator::Div), None);
+    assert_eq!(
+        ModularNumberSystem::new(7u32).identity(Operator::Div),
+        Some(1)
+    );
+}
+#[test]
+fn test_rem() {
+    assert_eq!(NormalNumberSystem.rem(17u32, 5), Some(2));
+    assert_eq!(NormalNumberSystem.rem(17u32, 0), None);
+    assert_eq!(NormalNumberSystem.rem(3u32, 5), None);
+    let config = NormalNumberSystem::builder();
+    assert_eq!(config.rem(0u32, 0), None);
+    assert_eq!(17u8.checked_rem(0), None);
+    let system = ModularNumberSystem::new(7u32);
+    assert_eq!(system.rem(6, 4), Some(2));
+    assert_eq!(system.rem(6, 0), None);
+}
+#[test]
+fn test_check_value() {
+    assert_eq!(NormalNumberSystem.check_value(u8::MAX), Ok(()));
+    let system = ModularNumberSystem::new(7u32);
+    assert_eq!(system.check_value(6), Ok(()));
+    assert_eq!(
+        system.check_value(7),
+        Err(String::from(
+            "7 is not a residue modulo 7, which must be from 0 to 6"
+        ))
+    );
+    assert!(ModularNumberSystem::new(7i32).check_value(-1).is_err());
+    assert_eq!(NormalNumberSystem.reduce(9u32), 9);
+    assert_eq!(system.reduce(9), 2);
+    assert_eq!(ModularNumberSystem::new(7i32).reduce(-1), 6);
+}
+#[test]
+fn test_saturating_number_system() {
+    let system = SaturatingNumberSystem;
+    assert_eq!(system.add(200u8, 100), Some(255));
+    assert_eq!(system.mul(30u8, 20), Some(255));
+    assert_eq!(system.sub(100i8, -100), Some(127));
+    assert_eq!(system.add(3u8, 2), Some(5));
+    // The operands follow the same rules as the normal number system.
+    assert_eq!(system.add(2u8, 3), None);
+    assert_eq!(system.sub(3u8, 5), None);
+    assert_eq!(system.mul(5u8, 1), None);
+    assert_eq!(system.add(5u8, 0), None);
+    assert_eq!(system.div(7u8, 2), None);
+    assert_eq!(system.div(8u8, 0), None);
+    assert_eq!(system.pow(2u8, 10), None);
+    // The saturated value is used as is by later steps.
+    assert_eq!(
+        Operator::Sub.apply(&system, system.add(200u8, 100).unwrap(), 55),
+        Some(200)
+    );
+}
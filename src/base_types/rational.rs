@@ -0,0 +1,360 @@
+//! # Rational Numbers
+//! Exact fractions over any integer `NumberType`, which allows searching with
+//! division that never truncates.
+
+use core::{
+    cmp::Ordering,
+    fmt::Display,
+    iter::Sum,
+    ops::{
+        Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub,
+        SubAssign,
+    },
+};
+
+use super::numbers::{
+    CheckedOperations, NumberSystem, NumberType, checked_concat, checked_pow,
+};
+#[cfg(feature = "parsing")]
+use crate::parsing::{ParsableNumber, Token};
+
+/// A fraction which is always stored in lowest terms with a positive
+/// denominator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rational<T: NumberType> {
+    numerator: T,
+    denominator: T,
+}
+
+impl<T: NumberType> Rational<T> {
+    /// Creates the reduced fraction `numerator / denominator`.
+    ///
+    /// # Panics
+    /// Panics if the denominator is zero.
+    pub fn new(numerator: T, denominator: T) -> Self {
+        Self::checked_new(numerator, denominator)
+            .expect("Rational with a zero denominator")
+    }
+    /// Creates the reduced fraction `numerator / denominator`, returning
+    /// `None` if the denominator is zero.
+    pub fn checked_new(mut numerator: T, mut denominator: T) -> Option<Self> {
+        if denominator == T::ZERO {
+            return None;
+        }
+        let divisor = numerator.gcd(denominator);
+        numerator /= divisor;
+        denominator /= divisor;
+        if denominator < T::ZERO {
+            numerator = T::ZERO.checked_sub(numerator)?;
+            denominator = T::ZERO.checked_sub(denominator)?;
+        }
+        Some(Self {
+            numerator,
+            denominator,
+        })
+    }
+    /// Gets the numerator of the reduced fraction
+    pub fn numerator(&self) -> T {
+        self.numerator
+    }
+    /// Gets the (positive) denominator of the reduced fraction
+    pub fn denominator(&self) -> T {
+        self.denominator
+    }
+    /// Whether the fraction represents a whole number
+    pub fn is_integer(&self) -> bool {
+        self.denominator == T::ONE
+    }
+}
+impl<T: NumberType> From<T> for Rational<T> {
+    fn from(value: T) -> Self {
+        Self {
+            numerator: value,
+            denominator: T::ONE,
+        }
+    }
+}
+/// Reads a whole number `3` or a fraction `3/2`. A `/` directly after a
+/// number is always read as part of the fraction.
+#[cfg(feature = "parsing")]
+impl<T: ParsableNumber> ParsableNumber for Rational<T> {
+    fn from_tokens(
+        tokens: &mut alloc::collections::VecDeque<Token>,
+    ) -> Result<Self, String> {
+        let numerator = T::from_tokens(tokens)?;
+        if tokens.front() != Some(&Token::Punctuation('/')) {
+            return Ok(Self::from(numerator));
+        }
+        tokens.pop_front();
+        let denominator = T::from_tokens(tokens)?;
+        Self::checked_new(numerator, denominator).ok_or_else(|| {
+            format!("The fraction {numerator}/{denominator} is undefined")
+        })
+    }
+}
+impl<T: NumberType> Display for Rational<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_integer() {
+            Display::fmt(&self.numerator, f)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+impl<T: NumberType> PartialOrd for Rational<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// Compares the fractions by their continued fractions, so no product of a
+/// numerator and a denominator is ever needed and nothing can overflow.
+impl<T: NumberType> Ord for Rational<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (mut left, mut right) = (*self, *other);
+        // Set once the terms being compared are reciprocals of the fractions,
+        // which reverses the order.
+        let mut reversed = false;
+        loop {
+            let (left_whole, left_rest) =
+                floor_div_rem(left.numerator, left.denominator);
+            let (right_whole, right_rest) =
+                floor_div_rem(right.numerator, right.denominator);
+            let order = match (left_rest == T::ZERO, right_rest == T::ZERO) {
+                | _ if left_whole != right_whole => {
+                    left_whole.cmp(&right_whole)
+                }
+                | (true, true) => Ordering::Equal,
+                | (true, false) => Ordering::Less,
+                | (false, true) => Ordering::Greater,
+                | (false, false) => {
+                    // Both remainders lie strictly between 0 and 1, so
+                    // compare their reciprocals instead.
+                    left = Self {
+                        numerator: left.denominator,
+                        denominator: left_rest,
+                    };
+                    right = Self {
+                        numerator: right.denominator,
+                        denominator: right_rest,
+                    };
+                    reversed = !reversed;
+                    continue;
+                }
+            };
+            return if reversed { order.reverse() } else { order };
+        }
+    }
+}
+/// Divides by a positive `denominator`, rounding the quotient down so the
+/// remainder is never negative.
+fn floor_div_rem<T: NumberType>(numerator: T, denominator: T) -> (T, T) {
+    let (whole, rest) = (numerator / denominator, numerator % denominator);
+    if rest < T::ZERO {
+        // The denominator is at least 2 here, so neither step can overflow.
+        (whole - T::ONE, rest + denominator)
+    } else {
+        (whole, rest)
+    }
+}
+impl<T: NumberType> CheckedOperations for Rational<T> {
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numerator
+                .checked_mul(rhs.denominator)?
+                .checked_add(rhs.numerator.checked_mul(self.denominator)?)?,
+            self.denominator.checked_mul(rhs.denominator)?,
+        )
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numerator
+                .checked_mul(rhs.denominator)?
+                .checked_sub(rhs.numerator.checked_mul(self.denominator)?)?,
+            self.denominator.checked_mul(rhs.denominator)?,
+        )
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numerator.checked_mul(rhs.numerator)?,
+            self.denominator.checked_mul(rhs.denominator)?,
+        )
+    }
+
+    fn checked_div(self, rhs: Self) -> Option<Self> {
+        Self::checked_new(
+            self.numerator.checked_mul(rhs.denominator)?,
+            self.denominator.checked_mul(rhs.numerator)?,
+        )
+    }
+
+    /// The remainder after subtracting a whole multiple of `rhs`, which
+    /// takes the sign of `self` like integer remainders.
+    fn checked_rem(self, rhs: Self) -> Option<Self> {
+        let quotient = self.checked_div(rhs)?;
+        let whole = Self::from(quotient.numerator / quotient.denominator);
+        self.checked_sub(whole.checked_mul(rhs)?)
+    }
+}
+macro_rules! impl_operator {
+    (
+        $trait:ident,
+        $f:ident,
+        $checked:ident,
+        $assign_trait:ident,
+        $assign:ident
+    ) => {
+        impl<T: NumberType> $trait for Rational<T> {
+            type Output = Self;
+
+            fn $f(self, rhs: Self) -> Self {
+                self.$checked(rhs).expect(concat!(
+                    "attempt to ",
+                    stringify!($f),
+                    " with overflow"
+                ))
+            }
+        }
+        impl<T: NumberType> $assign_trait for Rational<T> {
+            fn $assign(&mut self, rhs: Self) {
+                *self = $trait::$f(*self, rhs);
+            }
+        }
+    };
+}
+impl_operator!(Add, add, checked_add, AddAssign, add_assign);
+impl_operator!(Sub, sub, checked_sub, SubAssign, sub_assign);
+impl_operator!(Mul, mul, checked_mul, MulAssign, mul_assign);
+impl_operator!(Div, div, checked_div, DivAssign, div_assign);
+impl_operator!(Rem, rem, checked_rem, RemAssign, rem_assign);
+impl<T: NumberType> Sum for Rational<T> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+impl<T: NumberType> NumberType for Rational<T> {
+    const ONE: Self = Self {
+        numerator: T::ONE,
+        denominator: T::ONE,
+    };
+    const ZERO: Self = Self {
+        numerator: T::ZERO,
+        denominator: T::ONE,
+    };
+}
+
+/// A number system over exact fractions, where division by anything other
+/// than zero always succeeds.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RationalNumberSystem;
+impl<T: NumberType> NumberSystem<Rational<T>> for RationalNumberSystem {
+    fn add(&self, one: Rational<T>, other: Rational<T>) -> Option<Rational<T>> {
+        one.checked_add(other)
+    }
+
+    fn sub(&self, one: Rational<T>, other: Rational<T>) -> Option<Rational<T>> {
+        one.checked_sub(other)
+    }
+
+    fn mul(&self, one: Rational<T>, other: Rational<T>) -> Option<Rational<T>> {
+        one.checked_mul(other)
+    }
+
+    fn div(&self, one: Rational<T>, other: Rational<T>) -> Option<Rational<T>> {
+        one.checked_div(other)
+    }
+
+    fn pow(&self, one: Rational<T>, other: Rational<T>) -> Option<Rational<T>> {
+        if !other.is_integer() {
+            return None;
+        }
+        Some(Rational {
+            numerator: checked_pow(one.numerator, other.numerator)?,
+            denominator: checked_pow(one.denominator, other.numerator)?,
+        })
+    }
+
+    fn concat(
+        &self,
+        one: Rational<T>,
+        other: Rational<T>,
+    ) -> Option<Rational<T>> {
+        (one.is_integer() && other.is_integer())
+            .then(|| checked_concat(one.numerator, other.numerator))
+            .flatten()
+            .map(Rational::from)
+    }
+}
+
+#[test]
+fn test_rational_reduction() {
+    let sum = Rational::new(1i32, 3) + Rational::new(1, 6);
+    assert_eq!(sum, Rational::new(1, 2));
+    assert_eq!((sum.numerator(), sum.denominator()), (1, 2));
+    assert_eq!(Rational::new(4i32, -6), Rational::new(-2, 3));
+    assert_eq!(Rational::checked_new(1u32, 0), None);
+}
+#[test]
+fn test_rational_number_system() {
+    let system = RationalNumberSystem;
+    let (one, other) = (Rational::from(3u32), Rational::from(2u32));
+    assert_eq!(system.div(one, other), Some(Rational::new(3, 2)));
+    assert_eq!(system.div(one, Rational::ZERO), None);
+    assert_eq!(system.sub(other, one), None);
+    assert_eq!(
+        system.pow(Rational::new(2, 3), other),
+        Some(Rational::new(4, 9))
+    );
+    assert!(Rational::new(1u32, 3) < Rational::new(1, 2));
+    assert_eq!(
+        Rational::new(7u32, 2) % Rational::from(2),
+        Rational::new(3, 2)
+    );
+}
+#[test]
+fn test_rational_display() {
+    assert_eq!(Rational::new(3u32, 2).to_string(), "3/2");
+    assert_eq!(Rational::new(6u32, 2).to_string(), "3");
+}
+#[cfg(feature = "parsing")]
+#[test]
+fn test_parse_rational() {
+    use crate::parsing::Parsable;
+    assert_eq!(Rational::<i32>::parse_line("3/2"), Ok(Rational::new(3, 2)));
+    assert_eq!(
+        Rational::<i32>::parse_line("-6/4"),
+        Ok(Rational::new(-3, 2))
+    );
+    assert_eq!(
+        Vec::<Rational<u32>>::parse_line("[1/2, 3, 0x10/4]"),
+        Ok(vec![
+            Rational::new(1, 2),
+            Rational::from(3),
+            Rational::from(4)
+        ])
+    );
+    assert!(Rational::<u32>::parse_line("1/0").is_err());
+    assert!(Rational::<u32>::parse_line("1/").is_err());
+}
+#[test]
+fn test_ordering_near_max() {
+    assert!(Rational::new(1u8, 200) < Rational::new(200, 1));
+    assert!(Rational::new(254u8, 255) < Rational::new(255, 254));
+    assert!(Rational::new(253u8, 254) < Rational::new(254, 255));
+    assert!(Rational::new(-126i8, 127) < Rational::new(-125, 126));
+    assert!(Rational::new(-127i8, 126) < Rational::new(-1, 1));
+    assert!(Rational::new(127i8, 2) > Rational::new(-127, 3));
+    assert_eq!(
+        Rational::new(u64::MAX - 1, u64::MAX)
+            .cmp(&Rational::new(u64::MAX - 1, u64::MAX)),
+        Ordering::Equal
+    );
+    // Agrees with cross multiplication wherever that does not overflow.
+    for (a, b, c, d) in [(1i64, 3, 2, 7), (-5, 4, -9, 7), (7, 1, 14, 2)] {
+        assert_eq!(
+            Rational::new(a, b).cmp(&Rational::new(c, d)),
+            (a * d).cmp(&(c * b))
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! # Shared Expressions
+//! An [`Expression`] whose subexpressions are reference counted, so the
+//! generator can reuse a subtree in every application built on it instead of
+//! deep-cloning it each time.
+
+use alloc::{boxed::Box, rc::Rc};
+use core::fmt::Display;
+
+use super::{
+    expressions::{Expression, ExpressionNode, Operator},
+    numbers::NumberType,
+};
+
+/// An expression like [`Expression`], but with `Rc` children. Cloning one
+/// only clones the handles of its operands.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RcExpression<T: NumberType> {
+    /// Stores a value
+    Value(T),
+    /// Stores the application of an operator over two shared expressions and
+    /// the result there-of
+    Application(T, Operator, Rc<RcExpression<T>>, Rc<RcExpression<T>>),
+}
+impl<T: NumberType> RcExpression<T> {
+    /// Gets the value of the expression
+    pub fn get_value(&self) -> &T {
+        match self {
+            | RcExpression::Value(t) => t,
+            | RcExpression::Application(t, ..) => t,
+        }
+    }
+    /// Counts every value and application in the expression, counting a
+    /// shared subtree once for each place it is used.
+    pub fn node_count(&self) -> usize {
+        match self {
+            | RcExpression::Value(_) => 1,
+            | RcExpression::Application(_, _, left, right) => {
+                1 + left.node_count() + right.node_count()
+            }
+        }
+    }
+    /// Copies the expression into an [`Expression`] which owns every node
+    pub fn to_expression(&self) -> Expression<T> {
+        match self {
+            | RcExpression::Value(t) => Expression::Value(*t),
+            | RcExpression::Application(t, operator, left, right) => {
+                Expression::Application(
+                    *t,
+                    *operator,
+                    Box::new(left.to_expression()),
+                    Box::new(right.to_expression()),
+                )
+            }
+        }
+    }
+}
+impl<T: NumberType> From<&Expression<T>> for RcExpression<T> {
+    fn from(value: &Expression<T>) -> Self {
+        match value {
+            | Expression::Value(t) => RcExpression::Value(*t),
+            | Expression::Application(t, operator, left, right) => {
+                RcExpression::Application(
+                    *t,
+                    *operator,
+                    Rc::new(left.as_ref().into()),
+                    Rc::new(right.as_ref().into()),
+                )
+            }
+        }
+    }
+}
+/// The generator shares each node through its `Rc`, so that is what it
+/// builds applications from.
+impl<T: NumberType> ExpressionNode<T> for Rc<RcExpression<T>> {
+    fn leaf(value: T) -> Self {
+        Rc::new(RcExpression::Value(value))
+    }
+
+    fn application(
+        value: T,
+        operator: Operator,
+        left: &Self,
+        right: &Self,
+    ) -> Self {
+        Rc::new(RcExpression::Application(
+            value,
+            operator,
+            Rc::clone(left),
+            Rc::clone(right),
+        ))
+    }
+
+    fn value(&self) -> T {
+        *self.get_value()
+    }
+
+    fn operands(&self) -> Option<(Operator, &Self, &Self)> {
+        match self.as_ref() {
+            | RcExpression::Value(_) => None,
+            | RcExpression::Application(_, operator, left, right) => {
+                Some((*operator, left, right))
+            }
+        }
+    }
+}
+/// Displays the expression exactly like the [`Expression`] it represents
+impl<T: NumberType> Display for RcExpression<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            | RcExpression::Value(t) => Display::fmt(t, f),
+            | RcExpression::Application(_, operator, left, right) => {
+                if f.alternate() {
+                    write!(f, "(")?
+                }
+                write!(f, "{:#}", left)?;
+                write!(f, " ")?;
+                Display::fmt(operator, f)?;
+                write!(f, " ")?;
+                write!(f, "{:#}", right)?;
+                if f.alternate() {
+                    write!(f, ")")?
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rc_expression_display() {
+    let value = |t| Box::new(Expression::Value(t));
+    let sum = Expression::Application(5u32, Operator::Add, value(2), value(3));
+    let expr = Expression::Application(
+        20,
+        Operator::Mul,
+        Box::new(sum.clone()),
+        Box::new(Expression::Application(
+            4,
+            Operator::Sub,
+            value(6),
+            value(2),
+        )),
+    );
+    let shared = RcExpression::from(&expr);
+    assert_eq!(shared.to_string(), expr.to_string());
+    assert_eq!(format!("{shared:#}"), format!("{expr:#}"));
+    assert_eq!(shared.to_expression(), expr);
+    assert_eq!(shared.get_value(), &20);
+    assert_eq!(shared.node_count(), expr.node_count());
+}
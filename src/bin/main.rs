@@ -1,18 +1,24 @@
 use std::{
+    collections::{HashMap, VecDeque},
     fmt::Display,
-    io::{BufRead, Write, stderr, stdin},
+    io::{BufRead, BufWriter, Write, stderr, stdin},
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use rust_countdown::{
     self,
     base_types::{
-        expressions::{Operator, Operators},
+        expressions::{Expression, Operator, Operators, operator_histogram},
         numbers::{ModularNumberSystem, NormalNumberSystem, NumberType},
     },
-    generators::expression_tree_generator::find_expressions,
-    parsing::{Parsable, token_reader},
+    generators::{
+        expression_tree_generator::{
+            SearchOptions, find_expressions, validate_inputs,
+        },
+        progress::{Progress, ProgressEvent, ProgressRate},
+    },
+    parsing::{Parsable, Token, token_reader},
     timing::{MyReciever, threaded::channel},
 };
 
@@ -29,7 +35,7 @@ fn get_input<S: Display>(question: S) -> Result<String, std::io::Error> {
 /// returns a string.
 fn ask<T: Parsable + Sized, S: Display>(question: S) -> Result<T, String> {
     match get_input(question) {
-        | Ok(a) => T::parse(&mut token_reader::read(a)?.into_iter().collect()),
+        | Ok(a) => T::parse_line(&a),
         | Err(e) => Err(e.to_string()),
     }
 }
@@ -50,35 +56,156 @@ fn get_number_system<T: NumberType>(modulus: T) -> NumberSystems<T> {
         NumberSystems::Modular(ModularNumberSystem::new(modulus))
     }
 }
+/// How the found expressions are ordered before they are printed
+#[derive(Clone, Copy, Debug, Default)]
+enum SortKey {
+    /// Fewest nodes first, then fewest distinct operators
+    #[default]
+    Simplicity,
+    /// The order in which the expressions were found
+    Discovery,
+}
+impl SortKey {
+    /// Sorts the expressions, keeping equal ones in discovery order.
+    fn sort<T: NumberType>(self, expressions: &mut [Expression<T>]) {
+        match self {
+            | SortKey::Simplicity => {
+                expressions.sort_by_key(|expr| {
+                    let operators = operator_histogram(expr)
+                        .into_iter()
+                        .filter(|&count| count > 0)
+                        .count();
+                    (expr.node_count(), operators)
+                })
+            }
+            | SortKey::Discovery => {}
+        }
+    }
+}
+/// How the found expressions are printed
+#[derive(Clone, Copy, Debug, Default)]
+enum OutputFormat {
+    /// The first 100 expressions on stderr for reading
+    #[default]
+    Text,
+    /// Every expression as a JSON array on stdout
+    Json,
+}
+impl Parsable for OutputFormat {
+    fn parse(tokens: &mut VecDeque<Token>) -> Result<Self, String> {
+        match tokens.pop_front() {
+            | Some(Token::Word(a)) if a == "text" => Ok(OutputFormat::Text),
+            | Some(Token::Word(a)) if a == "json" => Ok(OutputFormat::Json),
+            | a => Err(format!("Expected text or json found {a:?}")),
+        }
+    }
+}
+/// Escapes the string as the contents of a JSON string.
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            | '"' => escaped.push_str("\\\""),
+            | '\\' => escaped.push_str("\\\\"),
+            | c if c.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            | c => escaped.push(c),
+        }
+    }
+    escaped
+}
+/// Prints the expressions as a JSON array of objects with the value, the
+/// expression and its node count.
+fn print_json<T: NumberType>(expressions: &[Expression<T>]) {
+    let objects: Vec<String> = expressions
+        .iter()
+        .map(|expr| {
+            format!(
+                concat!(
+                    "{{\"value\":{},",
+                    "\"expression\":\"{}\",",
+                    "\"node_count\":{}}}"
+                ),
+                expr.get_value(),
+                escape_json(&expr.to_string()),
+                expr.node_count()
+            )
+        })
+        .collect();
+    println!("[{}]", objects.join(","));
+}
+/// How many expressions are printed as text
+const TEXT_LIMIT: usize = 100;
+/// Writes the first expressions with their values and structure. Giving a
+/// buffered `output` makes the whole dump a handful of writes.
+fn print_text<T: NumberType, W: Write>(
+    expressions: &[Expression<T>],
+    output: &mut W,
+) -> std::io::Result<()> {
+    let len = expressions.len().min(TEXT_LIMIT);
+    writeln!(output, "First {len} expressions:\n")?;
+    for item in expressions.iter().take(TEXT_LIMIT) {
+        writeln!(output, "{}\t {} \t {:?}", item.get_value(), item, item)?;
+    }
+    Ok(())
+}
+/// How often the progress of a search is reported
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+/// How much weight the latest interval has in the reported discovery rate
+const RATE_SMOOTHING: f64 = 0.3;
+/// Reports the progress of a search on stderr, along with the smoothed number
+/// of expressions found per second.
+fn report(event: ProgressEvent, rate: f64) {
+    eprintln!(
+        "Found {:?} expressions in {:.2}s ({rate:.1}/s)",
+        event.expressions_emitted,
+        event.elapsed.as_secs_f64()
+    );
+}
+/// Creates the progress reporter for a search, which is created on the
+/// search thread since the callback is not `Send`.
+fn progress() -> Progress<'static> {
+    let mut rate = ProgressRate::new(RATE_SMOOTHING);
+    Progress::new(PROGRESS_INTERVAL, move |event| {
+        report(event, rate.update(event))
+    })
+}
 fn run<T: NumberType + Parsable + Sync + Send>(
     source_numbers: Vec<T>,
     target_number: T,
     number_system: NumberSystems<T>,
     operators: Operators,
+    sort_key: SortKey,
+    format: OutputFormat,
 ) -> Result<(), String> {
     let (mut sender, receiver) = channel();
     let start = Instant::now();
     let t = match number_system {
         | NumberSystems::Normal(number_system) => {
             thread::spawn(move || {
+                let mut progress = progress();
                 find_expressions(
                     source_numbers,
                     &number_system,
                     target_number,
                     &operators,
                     &mut sender,
+                    SearchOptions::default().progress(&mut progress),
                 )
             })
         }
         | NumberSystems::Modular(number_system) => {
             thread::spawn(move || {
                 // let mut s = sender;
+                let mut progress = progress();
                 find_expressions(
                     source_numbers,
                     &number_system,
                     target_number,
                     &operators,
                     &mut sender,
+                    SearchOptions::default().progress(&mut progress),
                 )
             })
         }
@@ -87,21 +214,10 @@ fn run<T: NumberType + Parsable + Sync + Send>(
     let first = receiver.next();
     let first_time = Instant::now();
     let mut v = vec![];
-    let mut time = 1;
     if let Some(first) = first {
         v.push(first);
         eprintln!("First item found in {:?}", (first_time - start));
-        v.extend(receiver.enumerate().map(|(i, a)| {
-            if time * 10 <= (Instant::now() - start).as_secs() {
-                eprintln!(
-                    "Found {:?} expressions in {:.2}s",
-                    i + 1,
-                    (Instant::now() - start).as_secs_f64()
-                );
-                time += 1;
-            }
-            a
-        }));
+        v.extend(receiver);
     }
     let done = Instant::now();
     eprintln!(
@@ -110,30 +226,171 @@ fn run<T: NumberType + Parsable + Sync + Send>(
         if v.len() == 1 { "expr" } else { "expressions" },
         done.duration_since(start)
     );
-    let len = v.len().min(100);
-    eprintln!("First {len} expressions:\n");
-    for item in v.iter().take(100) {
-        eprintln!("{}\t {} \t {:?}", item.get_value(), item, item);
+    sort_key.sort(&mut v);
+    match format {
+        | OutputFormat::Text => {
+            let mut output = BufWriter::new(stderr().lock());
+            print_text(&v, &mut output)
+                .and_then(|()| output.flush())
+                .map_err(|e| e.to_string())?;
+        }
+        | OutputFormat::Json => print_json(&v),
     }
 
     t.join().unwrap();
     Ok(())
 }
+/// The flags given on the command line, keyed by their name without the
+/// leading dashes.
+struct Args(HashMap<String, String>);
+impl Args {
+    /// The flags which take a value
+    const VALUED: [&str; 5] = ["numbers", "target", "modulus", "ops", "format"];
+    /// The flags which are either present or absent
+    const SWITCHES: [&str; 1] = ["discovery-order"];
+
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut flags = HashMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Expected a flag found {arg:?}"))?;
+            if Self::SWITCHES.contains(&name) {
+                flags.insert(name.to_string(), String::new());
+            } else if Self::VALUED.contains(&name) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("Missing a value for --{name}"))?;
+                flags.insert(name.to_string(), value);
+            } else {
+                return Err(format!("Unknown flag --{name}"));
+            }
+        }
+        Ok(Self(flags))
+    }
+    fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+    /// Parses the value of the flag with `parse`, or asks the question if the
+    /// flag is absent.
+    fn get_or_ask<T: Parsable>(
+        &self,
+        name: &str,
+        parse: impl FnOnce(&str) -> Result<T, String>,
+        question: &str,
+    ) -> Result<T, String> {
+        match self.0.get(name) {
+            | Some(value) => parse(value).map_err(|e| format!("--{name} {e}")),
+            | None => ask(question),
+        }
+    }
+}
+/// Parses a run of operators such as `+-*/`, optionally separated by commas.
+fn parse_operators(line: &str) -> Result<Vec<Operator>, String> {
+    let mut tokens: VecDeque<Token> = token_reader::read_spanned(line)?
+        .into_iter()
+        .map(|(t, _)| t)
+        .filter(|t| *t != Token::Punctuation(','))
+        .collect();
+    let mut operators = Vec::new();
+    while !tokens.is_empty() {
+        operators.push(Operator::parse(&mut tokens)?);
+    }
+    Ok(operators)
+}
 /// A wrapper for main which allows easy modification of the default type.
 fn _main<T: NumberType + Parsable + Sync + Send>() -> Result<(), String> {
-    let source_numbers = ask::<Vec<T>, _>("Please enter the source numbers")?;
-    let target_number = ask::<T, _>("Please enter the target number")?;
-    let number_system =
-        get_number_system(ask::<T, _>("Please enter the modulus")?);
-    let operators = Operators::from_iter(ask::<Vec<Operator>, _>(
+    let args = Args::parse(std::env::args().skip(1))?;
+    let source_numbers = args.get_or_ask(
+        "numbers",
+        |value| Vec::<T>::parse_line(&format!("[{value}]")),
+        "Please enter the source numbers",
+    )?;
+    let target_number = args.get_or_ask(
+        "target",
+        T::parse_line,
+        "Please enter the target number",
+    )?;
+    let number_system = get_number_system(args.get_or_ask(
+        "modulus",
+        T::parse_line,
+        "Please enter the modulus",
+    )?);
+    match &number_system {
+        | NumberSystems::Normal(system) => {
+            validate_inputs(&source_numbers, system, target_number)
+        }
+        | NumberSystems::Modular(system) => {
+            validate_inputs(&source_numbers, system, target_number)
+        }
+    }?;
+    let operators = Operators::from_iter(args.get_or_ask(
+        "ops",
+        parse_operators,
         "Please enter the allowed operators",
     )?);
-    run(source_numbers, target_number, number_system, operators)
+    let sort_key = if args.has("discovery-order") {
+        SortKey::Discovery
+    } else {
+        SortKey::default()
+    };
+    let format = match args.0.get("format") {
+        | Some(value) => {
+            OutputFormat::parse_line(value)
+                .map_err(|e| format!("--format {e}"))?
+        }
+        | None => OutputFormat::default(),
+    };
+    run(
+        source_numbers,
+        target_number,
+        number_system,
+        operators,
+        sort_key,
+        format,
+    )
 }
 
 fn main() {
     match _main::<usize>() {
         | Ok(()) => {}
-        | Err(e) => eprintln!("Error: {e}"),
+        | Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[test]
+fn test_print_text_is_batched() {
+    /// Counts the writes which reach the underlying output
+    #[derive(Debug, Default)]
+    struct CountingWriter {
+        writes: usize,
+        bytes: Vec<u8>,
+    }
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes += 1;
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
     }
+    let expressions: Vec<_> = (0..150u32).map(Expression::Value).collect();
+    let mut unbuffered = CountingWriter::default();
+    print_text(&expressions, &mut unbuffered).unwrap();
+    let mut buffered = BufWriter::new(CountingWriter::default());
+    print_text(&expressions, &mut buffered).unwrap();
+    let buffered = buffered.into_inner().unwrap();
+    assert_eq!(buffered.bytes, unbuffered.bytes);
+    assert_eq!(
+        String::from_utf8(buffered.bytes).unwrap().lines().count(),
+        102
+    );
+    assert!(unbuffered.writes > TEXT_LIMIT);
+    assert!(buffered.writes < 5, "{} writes", buffered.writes);
 }
@@ -1,13 +1,44 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, VecDeque},
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
 use crate::{
     base_types::{
-        expressions::{Expression, Operators},
+        expressions::{
+            Expression, ExpressionNode, OPERATOR_COUNT, Operator, Operators,
+            valid_application,
+        },
         numbers::{NumberSystem, NumberType},
+        rc_expression::RcExpression,
     },
     timing::{MySender, caching::CachingTransciever},
 };
 
-use super::subset_permutation_generator::SubsetPermutationGenerator;
+use super::{
+    progress::Progress,
+    repeated_sequence_generator::RepeatedSequenceGenerator,
+    subset_generator::{KVPairIterator, KeyCount},
+    subset_permutation_generator::SubsetPermutationGenerator,
+};
 
+/// Sends every expression over the source numbers in order, within the
+/// limits of the options. Each of them uses one operation fewer than there
+/// are numbers, so nothing is generated if that is more than the maximum
+/// operations, and subtrees over the maximum cost are pruned as they are
+/// generated. There is only the one ordering, so `use_all` and progress
+/// reporting do not apply. With the `parallel` option the splits are
+/// generated on rayon's thread pool, and each expression is sent as soon as
+/// it arrives, in a nondeterministic order. With the `subtrees` option every
+/// run of neighbouring numbers is generated on its own, shortest first.
 pub fn generate_tree<
     T: NumberType,
     N: NumberSystem<T>,
@@ -17,61 +48,2200 @@ pub fn generate_tree<
     number_system: &N,
     results: &mut M,
     operators: &Operators,
+    options: SearchOptions<'_, '_>,
 ) {
-    if source_numbers.len() == 1 {
-        results.send(Expression::Value(source_numbers[0]));
+    let mut remaining = options.max_results.unwrap_or(usize::MAX);
+    let len = source_numbers.len();
+    if remaining == 0 || len == 0 {
         return;
     }
-    let mut left = CachingTransciever::default();
-    let mut right = CachingTransciever::default();
-    for mid in 1..(source_numbers.len()) {
-        let (l, r) = source_numbers.split_at(mid);
-        generate_tree(l, number_system, &mut left, operators);
-        generate_tree(r, number_system, &mut right, operators);
-        for left_expr in left.as_ref().iter() {
-            let left_value = left_expr.get_value();
-            for right_expr in right.as_ref().iter() {
-                let right_value = right_expr.get_value();
-                for oper in *operators {
-                    if let Some(a) =
-                        oper.apply(number_system, *left_value, *right_value)
-                    {
-                        if a == T::ZERO {
-                            continue;
-                        }
-                        let expr = Expression::Application(
-                            a,
-                            oper,
-                            Box::new(left_expr.clone()),
-                            Box::new(right_expr.clone()),
+    let cost_limit = options.cost_limit();
+    // Stop once the results can no longer be delivered.
+    let mut deliver = |expr| {
+        remaining -= 1;
+        results.send(expr) && remaining > 0
+    };
+    let mut stopped = false;
+    let sizes = if options.subtrees { 1..=len } else { len..=len };
+    for run in sizes.flat_map(|size| source_numbers.windows(size)) {
+        // The runs only grow, so none of the rest fit either.
+        if stopped || exceeds_operations(run, options.max_operations) {
+            return;
+        }
+        let mut on_expression = |expr| {
+            stopped = !deliver(expr);
+            !stopped
+        };
+        #[cfg(feature = "parallel")]
+        if options.parallel && run.len() > 1 {
+            generate_splits_parallel(
+                run,
+                number_system,
+                operators,
+                cost_limit.as_ref(),
+                &mut on_expression,
+            );
+            continue;
+        }
+        generate_with(
+            run,
+            number_system,
+            operators,
+            &AtomicBool::new(false),
+            cost_limit.as_ref(),
+            &mut |_, _, build| on_expression(build()),
+        );
+    }
+}
+/// Generates the expressions of each split of the numbers on rayon's thread
+/// pool, and hands each one to `deliver` on the calling thread as soon as it
+/// arrives. The workers are cancelled once `deliver` returns false.
+#[cfg(feature = "parallel")]
+fn generate_splits_parallel<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: &[T],
+    number_system: &N,
+    operators: &Operators,
+    cost_limit: Option<&CostLimit>,
+    deliver: &mut dyn FnMut(Expression<T>) -> bool,
+) {
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    let cancel = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::sync_channel(64);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            (1..source_numbers.len()).into_par_iter().for_each_with(
+                sender,
+                |sender, mid| {
+                    let (l, r) = source_numbers.split_at(mid);
+                    let (mut left, mut right) = (Vec::new(), Vec::new());
+                    for (numbers, cache) in [(l, &mut left), (r, &mut right)] {
+                        generate_with(
+                            numbers,
+                            number_system,
+                            operators,
+                            &cancel,
+                            cost_limit,
+                            &mut |_, cost, build| {
+                                cache.push((build(), cost));
+                                true
+                            },
                         );
-                        if expr.is_valid() {
-                            results.send(expr);
+                    }
+                    for (left_expr, left_cost) in &left {
+                        for (right_expr, right_cost) in &right {
+                            // A failed send means the receiver has stopped.
+                            let sent = combine(
+                                left_expr,
+                                right_expr,
+                                left_cost.checked_add(*right_cost),
+                                number_system,
+                                operators,
+                                cost_limit,
+                                &mut |_, _, build| {
+                                    !cancel.load(Ordering::Relaxed)
+                                        && sender.send(build()).is_ok()
+                                },
+                            );
+                            if !sent {
+                                return;
+                            }
                         }
                     }
+                },
+            );
+        });
+        // Dropping the receiver when this stops wakes any blocked worker.
+        for expr in receiver {
+            if !deliver(expr) {
+                cancel.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+    });
+}
+/// Sends the same expressions in the same order as [`generate_tree`], but as
+/// [`RcExpression`]s. Each subexpression is built once per split, and every
+/// application over it shares it instead of holding its own deep copy.
+pub fn generate_tree_shared<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<RcExpression<T>>,
+>(
+    source_numbers: &[T],
+    number_system: &N,
+    results: &mut M,
+    operators: &Operators,
+    options: SearchOptions<'_, '_>,
+) {
+    let mut remaining = options.max_results.unwrap_or(usize::MAX);
+    if remaining == 0
+        || exceeds_operations(source_numbers, options.max_operations)
+    {
+        return;
+    }
+    // Stop once the results can no longer be delivered.
+    generate_with(
+        source_numbers,
+        number_system,
+        operators,
+        &AtomicBool::new(false),
+        options.cost_limit().as_ref(),
+        &mut |_, _, build: &dyn Fn() -> Rc<RcExpression<T>>| {
+            remaining -= 1;
+            results.send(Rc::unwrap_or_clone(build())) && remaining > 0
+        },
+    );
+}
+/// Whether every expression over the numbers has more than `max_operations`
+/// operations.
+fn exceeds_operations<T>(numbers: &[T], max_operations: Option<usize>) -> bool {
+    max_operations.is_some_and(|k| numbers.len() > k + 1)
+}
+/// A cap on the total cost of the operators used by an expression, where each
+/// operator's cost is indexed by [`Operator::index`].
+///
+/// [`Operator::index`]: crate::base_types::expressions::Operator::index
+struct CostLimit {
+    costs: [u32; OPERATOR_COUNT],
+    max_cost: u32,
+}
+impl CostLimit {
+    /// The total cost of every operator application in the expression, or
+    /// `None` if it does not fit in a `u32`, which is over any budget. The
+    /// generator carries this alongside each subtree instead.
+    #[cfg(test)]
+    fn cost<T: NumberType, E: ExpressionNode<T>>(
+        &self,
+        expr: &E,
+    ) -> Option<u32> {
+        match expr.operands() {
+            | None => Some(0),
+            | Some((operator, left, right)) => {
+                self.costs[operator.index()]
+                    .checked_add(self.cost(left)?)?
+                    .checked_add(self.cost(right)?)
+            }
+        }
+    }
+    /// The total cost of applying `operator` to operands costing
+    /// `operand_cost` in total, or `None` if that goes over the budget.
+    fn applied_cost(
+        &self,
+        operand_cost: Option<u32>,
+        operator: Operator,
+    ) -> Option<u32> {
+        operand_cost
+            .and_then(|cost| cost.checked_add(self.costs[operator.index()]))
+            .filter(|cost| *cost <= self.max_cost)
+    }
+}
+/// Receives the value of a generated expression, the total cost of its
+/// operators under the cost limit (zero without one) and a function which
+/// builds it, returning whether the generation should continue.
+type OnExpression<'a, T, E = Expression<T>> =
+    dyn FnMut(T, u32, &dyn Fn() -> E) -> bool + 'a;
+/// Generates every valid expression over the source numbers in order, calling
+/// `on_expression` with the value of each one and a function which builds it.
+/// The expression is only cloned into a tree when `build` is called. The
+/// generation stops early once `cancel` is set or `on_expression` returns
+/// false.
+fn generate_with<T: NumberType, N: NumberSystem<T>, E: ExpressionNode<T>>(
+    source_numbers: &[T],
+    number_system: &N,
+    operators: &Operators,
+    cancel: &AtomicBool,
+    cost_limit: Option<&CostLimit>,
+    on_expression: &mut OnExpression<'_, T, E>,
+) {
+    if source_numbers.len() == 1 {
+        let value = number_system.reduce(source_numbers[0]);
+        on_expression(value, 0, &|| E::leaf(value));
+        return;
+    }
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for mid in 1..(source_numbers.len()) {
+        let (l, r) = source_numbers.split_at(mid);
+        for (numbers, cache) in [(l, &mut left), (r, &mut right)] {
+            generate_with(
+                numbers,
+                number_system,
+                operators,
+                cancel,
+                cost_limit,
+                &mut |_, cost, build| {
+                    cache.push((build(), cost));
+                    true
+                },
+            );
+        }
+        // Each subtree carries its own cost, so it is never recomputed.
+        for (left_expr, left_cost) in &left {
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            for (right_expr, right_cost) in &right {
+                if !combine(
+                    left_expr,
+                    right_expr,
+                    left_cost.checked_add(*right_cost),
+                    number_system,
+                    operators,
+                    cost_limit,
+                    on_expression,
+                ) {
+                    return;
                 }
             }
         }
 
-        left.as_mut().clear();
-        right.as_mut().clear();
+        left.clear();
+        right.clear();
+    }
+}
+/// Calls `on_expression` with every valid application of an operator to the
+/// two expressions, returning false if it asked to stop. `operand_cost` is
+/// the total cost of both expressions, or `None` if it overflowed.
+fn combine<T: NumberType, N: NumberSystem<T>, E: ExpressionNode<T>>(
+    left_expr: &E,
+    right_expr: &E,
+    operand_cost: Option<u32>,
+    number_system: &N,
+    operators: &Operators,
+    cost_limit: Option<&CostLimit>,
+    on_expression: &mut OnExpression<'_, T, E>,
+) -> bool {
+    let (left_value, right_value) = (left_expr.value(), right_expr.value());
+    for oper in *operators {
+        let cost = match cost_limit.map(|l| l.applied_cost(operand_cost, oper))
+        {
+            | Some(None) => continue,
+            | Some(Some(cost)) => cost,
+            | None => 0,
+        };
+        if let Some(a) = oper.apply(number_system, left_value, right_value) {
+            // Negative values carry on like any other, but zero is dropped
+            // unless the number system keeps it.
+            if !number_system.keeps_result(a) {
+                continue;
+            }
+            if valid_application(number_system, left_value, oper, right_expr)
+                && !on_expression(a, cost, &|| {
+                    E::application(a, oper, left_expr, right_expr)
+                })
+            {
+                return false;
+            }
+        }
+    }
+    true
+}
+/// Caches every expression over each multiset of numbers, in any order, so
+/// overlapping subproblems are only solved once. The memo belongs to a single
+/// number system and set of operators, since the cached expressions depend on
+/// both.
+///
+/// Every expression of every sub-multiset stays in memory for the lifetime of
+/// the memo, which for six numbers can be millions of trees. It trades that
+/// memory for not regenerating the shared subexpressions of each permutation.
+pub struct ExpressionMemo<'a, T: NumberType, N: NumberSystem<T>> {
+    number_system: &'a N,
+    operators: Operators,
+    cache: BTreeMap<Vec<T>, Vec<Expression<T>>>,
+}
+impl<'a, T: NumberType, N: NumberSystem<T>> ExpressionMemo<'a, T, N> {
+    /// Creates an empty memo for the number system and operators.
+    pub fn new(number_system: &'a N, operators: Operators) -> Self {
+        Self {
+            number_system,
+            operators,
+            cache: BTreeMap::new(),
+        }
+    }
+    /// Gets every valid expression which uses each of the numbers exactly
+    /// once, in any order.
+    pub fn expressions(&mut self, numbers: &[T]) -> &[Expression<T>] {
+        let mut key = numbers.to_vec();
+        key.sort();
+        self.fill(&key);
+        &self.cache[&key]
+    }
+    /// Makes sure the expressions of the sorted multiset are cached.
+    fn fill(&mut self, key: &[T]) {
+        if self.cache.contains_key(key) {
+            return;
+        }
+        let mut expressions = Vec::new();
+        if let [value] = key {
+            let value = self.number_system.reduce(*value);
+            expressions.push(Expression::Value(value));
+        }
+        // Each sub-multiset is a left side, with the rest on the right.
+        for left in
+            KVPairIterator::from(KeyCount::from_iter(key.iter().copied()))
+        {
+            let left: Vec<T> = left
+                .into_iter()
+                .flat_map(|(t, count)| std::iter::repeat_n(t, count))
+                .collect();
+            if left.len() == key.len() {
+                continue;
+            }
+            let right = difference(key, &left);
+            self.fill(&left);
+            self.fill(&right);
+            for left_expr in &self.cache[&left] {
+                for right_expr in &self.cache[&right] {
+                    combine(
+                        left_expr,
+                        right_expr,
+                        Some(0),
+                        self.number_system,
+                        &self.operators,
+                        None,
+                        &mut |_, _, build| {
+                            expressions.push(build());
+                            true
+                        },
+                    );
+                }
+            }
+        }
+        self.cache.insert(key.to_vec(), expressions);
+    }
+}
+/// Removes the items of `part` from `all`, where both are sorted and `part` is
+/// a sub-multiset of `all`.
+fn difference<T: NumberType>(all: &[T], part: &[T]) -> Vec<T> {
+    let mut part = part.iter().peekable();
+    all.iter()
+        .copied()
+        .filter(|t| part.next_if_eq(&t).is_none())
+        .collect()
+}
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], but builds them from an [`ExpressionMemo`] instead of
+/// regenerating each permutation. The results arrive grouped by the multiset
+/// of numbers they use.
+pub fn find_expressions_memoized<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
+>(
+    source_numbers: Vec<T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    sender: &mut M,
+) {
+    let mut memo = ExpressionMemo::new(number_system, *operators);
+    let subsets = KVPairIterator::from(KeyCount::from_iter(source_numbers));
+    'subsets: for subset in subsets {
+        let numbers: Vec<T> = subset
+            .into_iter()
+            .flat_map(|(t, count)| std::iter::repeat_n(t, count))
+            .collect();
+        for expr in memo.expressions(&numbers) {
+            if *expr.get_value() == target_number && !sender.send(expr.clone())
+            {
+                break 'subsets;
+            }
+        }
     }
+    sender.set_done();
 }
+/// Sends every expression over a subset of the source numbers which evaluates
+/// to the target, then marks the sender as done. See [`SearchOptions`] for
+/// ways to limit and follow the search. The source numbers can be any
+/// collection, such as an array or a range.
 pub fn find_expressions<
     T: NumberType,
     N: NumberSystem<T>,
     M: MySender<Expression<T>>,
+>(
+    source_numbers: impl IntoIterator<Item = T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    sender: &mut M,
+    options: SearchOptions<'_, '_>,
+) {
+    let permutations: Box<dyn Iterator<Item = Vec<T>>> = match options.reuse {
+        | Some(max_length) => {
+            Box::new(RepeatedSequenceGenerator::new(source_numbers, max_length))
+        }
+        | None if options.use_all => {
+            Box::new(SubsetPermutationGenerator::new(source_numbers).use_all())
+        }
+        | None => Box::new(SubsetPermutationGenerator::new(source_numbers)),
+    };
+    search_permutations(
+        permutations,
+        number_system,
+        |value| value == target_number,
+        operators,
+        sender,
+        &AtomicBool::new(false),
+        options,
+    );
+}
+/// A sender which hands every item to a closure, which returns whether to
+/// carry on.
+struct CallbackSender<F>(F);
+impl<T, F: FnMut(T) -> bool> MySender<T> for CallbackSender<F> {
+    fn send(&mut self, value: T) -> bool {
+        (self.0)(value)
+    }
+
+    fn set_done(&mut self) {}
+}
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], but calls `f` with each one on this thread instead
+/// of sending it. The search stops as soon as `f` returns `false`.
+pub fn find_expressions_with<
+    T: NumberType,
+    N: NumberSystem<T>,
+    F: FnMut(Expression<T>) -> bool,
+>(
+    source_numbers: impl IntoIterator<Item = T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    f: F,
+) {
+    find_expressions(
+        source_numbers,
+        number_system,
+        target_number,
+        operators,
+        &mut CallbackSender(f),
+        SearchOptions::default(),
+    );
+}
+/// Finds the expressions whose value is anywhere from `lo` to `hi` inclusive,
+/// like [`find_expressions`] with a range of targets.
+pub fn find_expressions_in_range<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
+>(
+    source_numbers: impl IntoIterator<Item = T>,
+    number_system: &N,
+    lo: T,
+    hi: T,
+    operators: &Operators,
+    sender: &mut M,
+) {
+    search_permutations(
+        SubsetPermutationGenerator::new(source_numbers),
+        number_system,
+        |value| lo <= value && value <= hi,
+        operators,
+        sender,
+        &AtomicBool::new(false),
+        SearchOptions::default(),
+    );
+}
+/// Finds one expression which evaluates to the target like
+/// [`find_expressions`], stopping the generator as soon as it is found. This
+/// answers whether the target is reachable at all without paying for the rest
+/// of the search.
+pub fn first_solution<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: impl IntoIterator<Item = T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+) -> Option<Expression<T>> {
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        source_numbers,
+        number_system,
+        target_number,
+        operators,
+        &mut results,
+        SearchOptions::default().max_results(1),
+    );
+    results.as_mut().pop_front()
+}
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], with source numbers of a narrower type which are
+/// widened into the working type `T` once before the search. Every
+/// intermediate result is then computed in `T`, so `u8` sources can reach a
+/// `u32` target above `255`.
+pub fn find_expressions_widened<
+    S,
+    T: NumberType + From<S>,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
+>(
+    source_numbers: impl IntoIterator<Item = S>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    sender: &mut M,
+    options: SearchOptions<'_, '_>,
+) {
+    let source_numbers: Vec<T> =
+        source_numbers.into_iter().map(T::from).collect();
+    find_expressions(
+        source_numbers,
+        number_system,
+        target_number,
+        operators,
+        sender,
+        options,
+    );
+}
+/// Checks that the source numbers and the target are all values of the number
+/// system before searching, since a target outside of it can never be found
+/// and operands outside of it break the system's assumptions.
+pub fn validate_inputs<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: &[T],
+    number_system: &N,
+    target_number: T,
+) -> Result<(), String> {
+    for number in source_numbers {
+        number_system
+            .check_value(*number)
+            .map_err(|e| format!("Source number {number} is unusable: {e}"))?;
+    }
+    number_system.check_value(target_number).map_err(|e| {
+        format!("Target {target_number} can never be reached: {e}")
+    })
+}
+/// Optional limits and progress reporting for [`find_expressions`]. The
+/// default searches everything without reporting.
+#[derive(Default)]
+pub struct SearchOptions<'a, 'p> {
+    max_results: Option<usize>,
+    max_operations: Option<usize>,
+    operator_costs: [u32; OPERATOR_COUNT],
+    max_cost: Option<u32>,
+    use_all: bool,
+    reuse: Option<usize>,
+    #[cfg(feature = "parallel")]
+    parallel: bool,
+    subtrees: bool,
+    progress: Option<&'a mut Progress<'p>>,
+}
+impl<'a, 'p> SearchOptions<'a, 'p> {
+    /// Stops the search once `max_results` expressions have been sent.
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+    /// Only searches expressions with at most `max_operations` operations.
+    /// An expression over `n` numbers always has `n - 1` operations, so
+    /// longer orderings are skipped before anything is generated for them.
+    pub fn max_operations(mut self, max_operations: usize) -> Self {
+        self.max_operations = Some(max_operations);
+        self
+    }
+    /// Sets how much each operator costs towards [`max_cost`], indexed by
+    /// [`Operator::index`]. Every operator costs nothing by default.
+    ///
+    /// [`max_cost`]: Self::max_cost
+    /// [`Operator::index`]: crate::base_types::expressions::Operator::index
+    pub fn operator_costs(mut self, costs: [u32; OPERATOR_COUNT]) -> Self {
+        self.operator_costs = costs;
+        self
+    }
+    /// Only searches expressions whose operators cost at most `max_cost` in
+    /// total. Unlike [`max_operations`](Self::max_operations) the operators
+    /// can cost different amounts, and subexpressions which are already too
+    /// expensive are pruned while they are generated.
+    pub fn max_cost(mut self, max_cost: u32) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+    /// Only searches expressions which use every source number exactly once,
+    /// rather than any subset of them.
+    pub fn use_all(mut self, use_all: bool) -> Self {
+        self.use_all = use_all;
+        self
+    }
+    /// Lets each source number be used any number of times, in expressions
+    /// of up to `max_length` numbers, instead of each at most once. See
+    /// [`RepeatedSequenceGenerator`] for how quickly this grows. Every
+    /// number is optional, so [`use_all`](Self::use_all) does not apply.
+    pub fn reuse(mut self, max_length: usize) -> Self {
+        self.reuse = Some(max_length);
+        self
+    }
+    /// Generates the splits of [`generate_tree`] on rayon's thread pool,
+    /// sending the expressions in whichever order they are finished. The
+    /// searches spread their orderings over threads with
+    /// [`find_expressions_parallel`] instead.
+    #[cfg(feature = "parallel")]
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+    /// Makes [`generate_tree`] send every subexpression of the trees rather
+    /// than only whole trees. A subexpression covers a run of neighbouring
+    /// numbers, so each run is generated on its own, shortest runs first, and
+    /// every tree over it is sent once. Filtering for a target then finds
+    /// one of the shortest ways to reach it first.
+    pub fn subtrees(mut self, subtrees: bool) -> Self {
+        self.subtrees = subtrees;
+        self
+    }
+    /// Updates `progress` as the search goes.
+    pub fn progress(mut self, progress: &'a mut Progress<'p>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+    /// The budget set by [`max_cost`](Self::max_cost), if there is one.
+    fn cost_limit(&self) -> Option<CostLimit> {
+        self.max_cost.map(|max_cost| {
+            CostLimit {
+                costs: self.operator_costs,
+                max_cost,
+            }
+        })
+    }
+}
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], stopping early once `cancel` is set. The sender is
+/// still marked as done when the search is cancelled.
+pub fn find_expressions_cancellable<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
 >(
     source_numbers: Vec<T>,
     number_system: &N,
     target_number: T,
     operators: &Operators,
     sender: &mut M,
+    cancel: Arc<AtomicBool>,
 ) {
-    let mut _sender = sender.filter(move |a| *a.get_value() == target_number);
-    let mut sender_ = _sender.blocked();
-    for permutation in SubsetPermutationGenerator::new(source_numbers) {
-        generate_tree(&permutation, number_system, &mut sender_, operators);
+    search_permutations(
+        SubsetPermutationGenerator::new(source_numbers),
+        number_system,
+        |value| value == target_number,
+        operators,
+        sender,
+        &cancel,
+        SearchOptions::default(),
+    );
+}
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], but gives up once `deadline` has passed and returns
+/// whatever was found by then. The search runs on a scoped thread, which is
+/// always joined before returning.
+pub fn find_expressions_timeout<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: Vec<T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    deadline: Duration,
+) -> Vec<Expression<T>> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (finished, wait) = mpsc::channel();
+    let worker_cancel = cancel.clone();
+    thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            let mut results = CachingTransciever::default();
+            find_expressions_cancellable(
+                source_numbers,
+                number_system,
+                target_number,
+                operators,
+                &mut results,
+                worker_cancel,
+            );
+            let _ = finished.send(());
+            results
+        });
+        if wait.recv_timeout(deadline).is_err() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        let results = worker.join().expect("The search thread panicked");
+        results.0.into()
+    })
+}
+/// Lazily yields the expressions which evaluate to the target, like
+/// [`find_expressions`] but on the calling thread and without a channel. The
+/// two sides of each split are generated when the split is reached, and
+/// their combinations are then checked one pair at a time.
+pub struct ExpressionIterator<T: NumberType, N: NumberSystem<T>> {
+    permutations: SubsetPermutationGenerator<T>,
+    number_system: N,
+    target_number: T,
+    operators: Operators,
+    split: Option<Split<T>>,
+    pending: VecDeque<Expression<T>>,
+}
+/// The progress through the splits of a single permutation.
+struct Split<T: NumberType> {
+    numbers: Vec<T>,
+    mid: usize,
+    left: Vec<Expression<T>>,
+    right: Vec<Expression<T>>,
+    i: usize,
+    j: usize,
+}
+impl<T: NumberType, N: NumberSystem<T>> ExpressionIterator<T, N> {
+    /// Prepares a search, which only starts once the iterator is polled.
+    pub fn new(
+        source_numbers: Vec<T>,
+        number_system: N,
+        target_number: T,
+        operators: Operators,
+    ) -> Self {
+        Self {
+            permutations: SubsetPermutationGenerator::new(source_numbers),
+            number_system,
+            target_number,
+            operators,
+            split: None,
+            pending: VecDeque::new(),
+        }
+    }
+    /// Generates every expression over the numbers into a vector.
+    fn generate(&self, numbers: &[T]) -> Vec<Expression<T>> {
+        let mut expressions = Vec::new();
+        generate_with(
+            numbers,
+            &self.number_system,
+            &self.operators,
+            &AtomicBool::new(false),
+            None,
+            &mut |_, _, build| {
+                expressions.push(build());
+                true
+            },
+        );
+        expressions
+    }
+    /// Moves on to the next pair of the current split, or the next split once
+    /// the pairs run out. Returns false once the permutation is exhausted.
+    fn advance(&mut self) -> bool {
+        let Some(split) = self.split.as_mut() else {
+            return false;
+        };
+        if let (Some(left_expr), Some(right_expr)) =
+            (split.left.get(split.i), split.right.get(split.j))
+        {
+            let target_number = self.target_number;
+            let pending = &mut self.pending;
+            combine(
+                left_expr,
+                right_expr,
+                Some(0),
+                &self.number_system,
+                &self.operators,
+                None,
+                &mut |value, _, build| {
+                    if value == target_number {
+                        pending.push_back(build());
+                    }
+                    true
+                },
+            );
+            split.j += 1;
+            if split.j == split.right.len() {
+                (split.i, split.j) = (split.i + 1, 0);
+            }
+            return true;
+        }
+        split.mid += 1;
+        if split.mid == split.numbers.len() {
+            self.split = None;
+            return false;
+        }
+        let (l, r) = split.numbers.split_at(split.mid);
+        let (l, r) = (l.to_vec(), r.to_vec());
+        let (left, right) = (self.generate(&l), self.generate(&r));
+        let split = self.split.as_mut().unwrap();
+        (split.left, split.right, split.i, split.j) = (left, right, 0, 0);
+        true
+    }
+}
+impl<T: NumberType, N: NumberSystem<T>> Iterator for ExpressionIterator<T, N> {
+    type Item = Expression<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(expr) = self.pending.pop_front() {
+                return Some(expr);
+            }
+            if self.advance() {
+                continue;
+            }
+            let numbers = self.permutations.next()?;
+            if let [value] = numbers[..] {
+                let value = self.number_system.reduce(value);
+                if value == self.target_number {
+                    return Some(Expression::Value(value));
+                }
+                continue;
+            }
+            self.split = Some(Split {
+                numbers,
+                mid: 0,
+                left: Vec::new(),
+                right: Vec::new(),
+                i: 0,
+                j: 0,
+            });
+        }
+    }
+}
+/// Sends the expressions over each ordering of numbers whose value is a
+/// target, then marks the sender as done. Stops early once the search is
+/// cancelled or the options' limits are reached. In debug builds
+/// every expression is re-evaluated before it is sent, and one with the wrong
+/// value is a bug in the generator.
+fn search_permutations<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
+>(
+    permutations: impl Iterator<Item = Vec<T>>,
+    number_system: &N,
+    is_target: impl Fn(T) -> bool,
+    operators: &Operators,
+    sender: &mut M,
+    cancel: &AtomicBool,
+    options: SearchOptions<'_, '_>,
+) {
+    let cost_limit = options.cost_limit();
+    let SearchOptions {
+        max_results,
+        max_operations,
+        mut progress,
+        ..
+    } = options;
+    let mut remaining = max_results.unwrap_or(usize::MAX);
+    let mut stopped = remaining == 0;
+    let (mut permutations_done, mut emitted) = (0, 0);
+    for permutation in permutations {
+        if stopped || cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        if exceeds_operations(&permutation, max_operations) {
+            permutations_done += 1;
+            continue;
+        }
+        generate_with(
+            &permutation,
+            number_system,
+            operators,
+            cancel,
+            cost_limit.as_ref(),
+            &mut |value, _, build| {
+                if !is_target(value) {
+                    return true;
+                }
+                let expr: Expression<T> = build();
+                debug_assert!(
+                    expr.check(number_system),
+                    "Generated an expression with a wrong value: {expr:?}"
+                );
+                remaining -= 1;
+                emitted += 1;
+                stopped = !sender.send(expr) || remaining == 0;
+                if let Some(progress) = progress.as_mut() {
+                    progress.update(permutations_done, emitted);
+                }
+                !stopped
+            },
+        );
+        permutations_done += 1;
+        if let Some(progress) = progress.as_mut() {
+            progress.update(permutations_done, emitted);
+        }
     }
     sender.set_done();
 }
+/// Finds the expressions which evaluate to the target like
+/// [`find_expressions`], but spreads the permutations over a rayon thread pool
+/// with the given number of threads. The results arrive in a nondeterministic
+/// order.
+#[cfg(feature = "parallel")]
+pub fn find_expressions_parallel<
+    T: NumberType,
+    N: NumberSystem<T>,
+    M: MySender<Expression<T>>,
+>(
+    source_numbers: Vec<T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+    sender: &mut M,
+    threads: usize,
+) {
+    use rayon::{
+        ThreadPoolBuilder,
+        iter::{ParallelBridge, ParallelIterator},
+    };
+
+    use crate::timing::caching_async::ConcurrentCachingTransiever;
+
+    let shared = ConcurrentCachingTransiever::default();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("Could not build the thread pool");
+    pool.install(|| {
+        SubsetPermutationGenerator::new(source_numbers)
+            .par_bridge()
+            .for_each_with(shared.clone(), |shared, permutation| {
+                let mut local = CachingTransciever::default();
+                generate_tree(
+                    &permutation,
+                    number_system,
+                    &mut MySender::filter(&mut local, |a: &Expression<T>| {
+                        *a.get_value() == target_number
+                    }),
+                    operators,
+                    SearchOptions::default(),
+                );
+                for expr in local.as_mut().drain(..) {
+                    shared.send(expr);
+                }
+            })
+    });
+    for expr in shared.drain() {
+        sender.send(expr);
+    }
+    sender.set_done();
+}
+/// Counts the expressions which evaluate to the target without building the
+/// final expression trees.
+pub fn count_expressions<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: Vec<T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+) -> u64 {
+    let mut count = 0;
+    for permutation in SubsetPermutationGenerator::new(source_numbers) {
+        generate_with(
+            &permutation,
+            number_system,
+            operators,
+            &AtomicBool::new(false),
+            None,
+            &mut |value, _, _: &dyn Fn() -> Expression<T>| {
+                if value == target_number {
+                    count += 1;
+                }
+                true
+            },
+        );
+    }
+    count
+}
+/// A sender which only counts what it is sent.
+#[derive(Default)]
+struct CountingSender(usize);
+impl<T> MySender<T> for CountingSender {
+    fn send(&mut self, _: T) -> bool {
+        self.0 += 1;
+        true
+    }
+
+    fn set_done(&mut self) {}
+}
+/// Runs [`generate_tree`] into a sink which only counts the expressions, and
+/// returns how many were generated. Nothing is delivered anywhere, so timing
+/// this measures the cost of generating and building the trees on its own,
+/// for use from benchmarks and examples.
+pub fn bench_generate<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: &[T],
+    number_system: &N,
+    operators: &Operators,
+) -> usize {
+    let mut sink = CountingSender::default();
+    generate_tree(
+        source_numbers,
+        number_system,
+        &mut sink,
+        operators,
+        SearchOptions::default(),
+    );
+    sink.0
+}
+/// Finds the valid expression closest to the target, preferring fewer nodes
+/// when several are equally close. Returns `None` if there are no source
+/// numbers.
+pub fn find_nearest<T: NumberType, N: NumberSystem<T>>(
+    source_numbers: Vec<T>,
+    number_system: &N,
+    target_number: T,
+    operators: &Operators,
+) -> Option<Expression<T>> {
+    // Closer is greater, and a distance too large for the type is the
+    // farthest of all.
+    let mut best: Option<(Option<Reverse<T>>, usize, Expression<T>)> = None;
+    for permutation in SubsetPermutationGenerator::new(source_numbers) {
+        generate_with(
+            &permutation,
+            number_system,
+            operators,
+            &AtomicBool::new(false),
+            None,
+            &mut |value, _, build| {
+                let closeness = abs_diff(value, target_number).map(Reverse);
+                if best.as_ref().is_some_and(|(c, _, _)| closeness < *c) {
+                    return true;
+                }
+                let expr: Expression<T> = build();
+                let nodes = expr.node_count();
+                if best
+                    .as_ref()
+                    .is_none_or(|(c, n, _)| closeness > *c || nodes < *n)
+                {
+                    best = Some((closeness, nodes, expr));
+                }
+                true
+            },
+        );
+    }
+    best.map(|(_, _, expr)| expr)
+}
+/// The distance between two numbers, clamped at the bounds of the type so
+/// signed extremes do not overflow. Returns `None` for a type without bounds
+/// when the distance does not fit.
+fn abs_diff<T: NumberType>(one: T, other: T) -> Option<T> {
+    if one > other {
+        one.saturating_sub(other)
+    } else {
+        other.saturating_sub(one)
+    }
+}
+#[cfg(test)]
+fn has_inexact_division<T: NumberType>(expr: &Expression<T>) -> bool {
+    use crate::base_types::expressions::Operator;
+    match expr {
+        | Expression::Value(_) => false,
+        | Expression::Application(_, operator, left, right) => {
+            let (l, r) = (*left.get_value(), *right.get_value());
+            (*operator == Operator::Div && (r == T::ZERO || l % r != T::ZERO))
+                || has_inexact_division(left)
+                || has_inexact_division(right)
+        }
+    }
+}
+#[test]
+fn test_no_inexact_division() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![7u32, 2, 3, 5],
+        &NormalNumberSystem,
+        3,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    assert!(!results.as_ref().is_empty());
+    for expr in results.as_ref() {
+        assert!(!has_inexact_division(expr), "{expr}");
+        assert!(expr.check(&NormalNumberSystem), "{expr}");
+    }
+}
+#[test]
+fn test_count_expressions() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let source_numbers = vec![1u32, 2, 3, 4, 25];
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        source_numbers.clone(),
+        &NormalNumberSystem,
+        24,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let count = count_expressions(
+        source_numbers,
+        &NormalNumberSystem,
+        24,
+        &Operators::ALL,
+    );
+    assert!(count > 0);
+    assert_eq!(count, results.as_ref().len() as u64);
+}
+#[test]
+fn test_find_nearest() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let nearest =
+        find_nearest(vec![3u32, 7], &NormalNumberSystem, 23, &Operators::ALL);
+    assert_eq!(nearest.map(|a| *a.get_value()), Some(21));
+    let nearest =
+        find_nearest(vec![2u32, 4], &NormalNumberSystem, 3, &Operators::ALL)
+            .unwrap();
+    assert_eq!(nearest.node_count(), 1);
+    assert_eq!(
+        find_nearest(vec![], &NormalNumberSystem, 3u32, &Operators::ALL)
+            .map(|a| *a.get_value()),
+        None
+    );
+    // The distance from -2000000000 to the target does not fit in an i32.
+    let nearest = find_nearest(
+        vec![-2_000_000_000i32, 5],
+        &NormalNumberSystem,
+        2_000_000_000,
+        &Operators::ALL,
+    );
+    assert_eq!(nearest.map(|a| *a.get_value()), Some(2_000_000_005));
+    assert_eq!(abs_diff(i32::MIN, i32::MAX), Some(i32::MAX));
+    assert_eq!(abs_diff(i8::MAX, i8::MIN), Some(i8::MAX));
+}
+#[cfg(feature = "parallel")]
+#[test]
+fn test_find_expressions_parallel() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let source_numbers = vec![1u32, 2, 3, 4, 25];
+    let mut serial = CachingTransciever::default();
+    find_expressions(
+        source_numbers.clone(),
+        &NormalNumberSystem,
+        24,
+        &Operators::ALL,
+        &mut serial,
+        SearchOptions::default(),
+    );
+    let mut parallel = CachingTransciever::default();
+    find_expressions_parallel(
+        source_numbers,
+        &NormalNumberSystem,
+        24,
+        &Operators::ALL,
+        &mut parallel,
+        4,
+    );
+    let sorted = |results: CachingTransciever<Expression<u32>>| {
+        let mut results: Vec<String> =
+            results.0.iter().map(|a| format!("{a:?}")).collect();
+        results.sort();
+        results
+    };
+    assert_eq!(sorted(serial), sorted(parallel));
+}
+#[test]
+fn test_find_expressions_cancellable() {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    let cancel = Arc::new(AtomicBool::new(false));
+    let start = Instant::now();
+    let search = thread::spawn({
+        let cancel = cancel.clone();
+        move || {
+            let mut results = CachingTransciever::default();
+            find_expressions_cancellable(
+                vec![100u64, 75, 50, 25, 10, 9, 8, 7, 6, 3],
+                &NormalNumberSystem,
+                952,
+                &Operators::ALL,
+                &mut results,
+                cancel,
+            );
+            results
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    cancel.store(true, Ordering::Relaxed);
+    let mut results = search.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    results.as_mut().clear();
+    assert!(crate::timing::MyReciever::isdone(&results));
+}
+#[test]
+fn test_modular_zero_results() {
+    use crate::base_types::{
+        expressions::Operator, numbers::ModularNumberSystem,
+    };
+    let subtraction = Operators::from_iter([Operator::Sub]);
+    let system = ModularNumberSystem::new(7u32);
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![3, 3],
+        &system,
+        0,
+        &subtraction,
+        &mut results,
+        SearchOptions::default(),
+    );
+    assert!(results.as_ref().is_empty());
+    let system = system.with_zero_results(true);
+    find_expressions(
+        vec![3, 3],
+        &system,
+        0,
+        &subtraction,
+        &mut results,
+        SearchOptions::default(),
+    );
+    assert_eq!(results.as_ref().len(), 1);
+    assert_eq!(results.as_ref()[0].to_string(), "3 - 3");
+    assert!(results.as_ref()[0].is_valid_in(&system));
+    assert!(!results.as_ref()[0].is_valid_in(&system.with_zero_results(false)));
+}
+#[test]
+fn test_find_expressions_reuse() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let system =
+        NormalNumberSystem::builder().allow_commutative_duplicates(true);
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![2u32],
+        &system,
+        8,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default().reuse(3),
+    );
+    assert!(
+        results
+            .as_ref()
+            .iter()
+            .any(|a| a.to_string() == "(2 * 2) * 2")
+    );
+    assert!(results.as_ref().iter().all(|a| a.check(&system)));
+}
+#[test]
+fn test_find_expressions_max_results() {
+    use crate::{base_types::numbers::NormalNumberSystem, timing::MyReciever};
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![25u32, 50, 75, 100, 3, 6],
+        &NormalNumberSystem,
+        952,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default().max_results(5),
+    );
+    let results = results.collect_vec();
+    assert_eq!(results.len(), 5);
+    assert!(results.iter().all(|expr| *expr.get_value() == 952));
+}
+#[test]
+fn test_negative_results_are_reachable() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let system = NormalNumberSystem::builder().allow_negative_results(true);
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![3i32, 5],
+        &system,
+        -2,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> =
+        results.as_ref().iter().map(|e| e.to_string()).collect();
+    assert_eq!(found, ["3 - 5"]);
+    // A negative intermediate value can feed further operations.
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![3i32, 5, 4],
+        &system,
+        -8,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    assert!(
+        results
+            .as_ref()
+            .iter()
+            .any(|e| e.to_string() == "4 * (3 - 5)")
+    );
+}
+#[test]
+fn test_memoized_matches_uncached() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let sorted = |results: CachingTransciever<Expression<u32>>| {
+        let mut strings: Vec<String> =
+            results.as_ref().iter().map(|e| e.to_string()).collect();
+        strings.sort();
+        strings
+    };
+    let inputs = [vec![2, 3, 5, 7], vec![2, 2, 3, 7]];
+    for (numbers, target) in inputs.into_iter().zip([24, 35]) {
+        let mut uncached = CachingTransciever::default();
+        find_expressions(
+            numbers.clone(),
+            &NormalNumberSystem,
+            target,
+            &Operators::ALL,
+            &mut uncached,
+            SearchOptions::default(),
+        );
+        let mut cached = CachingTransciever::default();
+        find_expressions_memoized(
+            numbers,
+            &NormalNumberSystem,
+            target,
+            &Operators::ALL,
+            &mut cached,
+        );
+        assert!(!uncached.as_ref().is_empty());
+        assert_eq!(sorted(cached), sorted(uncached), "target {target}");
+    }
+}
+#[test]
+fn test_found_expressions_check() {
+    use crate::base_types::numbers::{ModularNumberSystem, NormalNumberSystem};
+    // A small linear congruential generator keeps the input reproducible.
+    let mut seed = 12345u32;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % 20 + 1
+    };
+    let numbers: Vec<u32> = (0..4).map(|_| next()).collect();
+    let target = next() * 3;
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        numbers.clone(),
+        &NormalNumberSystem,
+        target,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    for expr in results.as_ref() {
+        assert!(expr.check(&NormalNumberSystem), "{expr}");
+    }
+    let system = ModularNumberSystem::new(23);
+    let mut results = CachingTransciever::default();
+    let numbers: Vec<u32> = numbers.into_iter().map(|a| a % 23).collect();
+    find_expressions(
+        numbers,
+        &system,
+        5,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    assert!(!results.as_ref().is_empty());
+    for expr in results.as_ref() {
+        assert!(expr.check(&system), "{expr}");
+    }
+}
+#[test]
+fn test_find_expressions_progress() {
+    use std::{cell::Cell, time::Duration};
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    // Every reading of the fake clock advances it by a millisecond.
+    let now = Cell::new(0);
+    let mut events = Vec::new();
+    let mut progress = Progress::with_clock(
+        Duration::from_millis(5),
+        || {
+            now.set(now.get() + 1);
+            Duration::from_millis(now.get())
+        },
+        |event| events.push(event),
+    );
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![2u32, 3, 5, 7],
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default().progress(&mut progress),
+    );
+    drop(progress);
+    assert_eq!(events.len() as u64, now.get() / 5);
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event.elapsed, Duration::from_millis(5 * (i as u64 + 1)));
+    }
+    let last = events.last().unwrap();
+    assert!(last.expressions_emitted <= results.as_ref().len());
+    assert!(
+        events
+            .is_sorted_by_key(|e| (e.permutations_done, e.expressions_emitted))
+    );
+}
+#[test]
+fn test_expression_iterator_matches_channel() {
+    use crate::{
+        base_types::numbers::NormalNumberSystem,
+        timing::{MyReciever, threaded::channel},
+    };
+    let sorted = |expressions: Vec<Expression<u32>>| {
+        let mut strings: Vec<String> =
+            expressions.iter().map(|e| e.to_string()).collect();
+        strings.sort();
+        strings
+    };
+    for (numbers, target) in [(vec![2, 3, 5, 7], 24), (vec![3, 3, 4], 3)] {
+        let (mut sender, receiver) = channel();
+        let source = numbers.clone();
+        let search = std::thread::spawn(move || {
+            find_expressions(
+                source,
+                &NormalNumberSystem,
+                target,
+                &Operators::ALL,
+                &mut sender,
+                SearchOptions::default(),
+            )
+        });
+        let sent = receiver.collect_vec();
+        search.join().unwrap();
+        let iterated = ExpressionIterator::new(
+            numbers,
+            NormalNumberSystem,
+            target,
+            Operators::ALL,
+        )
+        .collect();
+        assert!(!sent.is_empty());
+        assert_eq!(sorted(iterated), sorted(sent), "target {target}");
+        let mut exhausted = ExpressionIterator::new(
+            vec![2u32, 3],
+            NormalNumberSystem,
+            target,
+            Operators::ALL,
+        );
+        exhausted.by_ref().for_each(drop);
+        assert!(exhausted.next().is_none());
+    }
+}
+#[test]
+fn test_max_operations() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        vec![2u32, 3, 4],
+        &NormalNumberSystem,
+        6,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default().max_operations(1),
+    );
+    let found: Vec<String> =
+        results.as_ref().iter().map(|e| e.to_string()).collect();
+    assert_eq!(found, ["3 * 2", "4 + 2"]);
+    let mut results = CachingTransciever::default();
+    generate_tree(
+        &[2u32, 3, 4],
+        &NormalNumberSystem,
+        &mut results,
+        &Operators::ALL,
+        SearchOptions::default().max_operations(1),
+    );
+    assert!(results.as_ref().is_empty());
+}
+#[test]
+fn test_find_expressions_timeout() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let numbers = vec![25u32, 50, 75, 100, 3, 6];
+    let mut full = CachingTransciever::default();
+    find_expressions(
+        numbers.clone(),
+        &NormalNumberSystem,
+        952,
+        &Operators::ALL,
+        &mut full,
+        SearchOptions::default(),
+    );
+    // A deadline which has already passed cancels the search long before
+    // it can get through every ordering.
+    let results = find_expressions_timeout(
+        numbers,
+        &NormalNumberSystem,
+        952,
+        &Operators::ALL,
+        Duration::ZERO,
+    );
+    assert!(results.len() < full.0.len(), "{} found", results.len());
+    assert!(results.iter().all(|expr| *expr.get_value() == 952));
+    let results = find_expressions_timeout(
+        vec![2u32, 3],
+        &NormalNumberSystem,
+        6,
+        &Operators::ALL,
+        Duration::from_secs(10),
+    );
+    assert_eq!(results.len(), 1);
+}
+#[test]
+fn test_use_all() {
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    let operators = Operators::from_iter([Operator::Add, Operator::Mul]);
+    let search = |use_all| {
+        let mut results = CachingTransciever::default();
+        find_expressions(
+            vec![1u32, 2, 3],
+            &NormalNumberSystem,
+            6,
+            &operators,
+            &mut results,
+            SearchOptions::default().use_all(use_all),
+        );
+        results.0
+    };
+    let all = search(true);
+    assert!(!all.is_empty());
+    assert!(all.iter().all(|expr| expr.leaves().len() == 3));
+    // Without the option `2 * 3` uses a subset.
+    assert!(search(false).iter().any(|expr| expr.leaves().len() == 2));
+}
+#[test]
+fn test_validate_inputs() {
+    use crate::base_types::numbers::{ModularNumberSystem, NormalNumberSystem};
+    assert_eq!(validate_inputs(&[1u8, 2], &NormalNumberSystem, 255), Ok(()));
+    let system = ModularNumberSystem::new(11u8);
+    assert_eq!(validate_inputs(&[1u8, 10], &system, 3), Ok(()));
+    let error = validate_inputs(&[1u8, 2], &system, 200).unwrap_err();
+    assert!(
+        error.starts_with("Target 200 can never be reached"),
+        "{error}"
+    );
+    let error = validate_inputs(&[1u8, 12], &system, 3).unwrap_err();
+    assert!(error.starts_with("Source number 12 is unusable"), "{error}");
+}
+#[test]
+fn test_single_number_solutions() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    for source in [vec![7u32, 3], vec![7]] {
+        let mut results = CachingTransciever::default();
+        find_expressions(
+            source,
+            &NormalNumberSystem,
+            7,
+            &Operators::ALL,
+            &mut results,
+            SearchOptions::default(),
+        );
+        assert!(results.0.contains(&Expression::Value(7)));
+    }
+    let mut iterator = ExpressionIterator::new(
+        vec![7u32, 3],
+        NormalNumberSystem,
+        7,
+        Operators::ALL,
+    );
+    assert!(iterator.any(|expr| expr == Expression::Value(7)));
+}
+#[test]
+fn test_max_cost() {
+    use crate::base_types::{
+        expressions::{Operator, operator_histogram},
+        numbers::NormalNumberSystem,
+    };
+    let search = |options: SearchOptions| {
+        let mut results = CachingTransciever::default();
+        find_expressions(
+            vec![12u32, 2, 3, 4],
+            &NormalNumberSystem,
+            6,
+            &Operators::ALL,
+            &mut results,
+            options,
+        );
+        results.0
+    };
+    let uses_division = |expr: &Expression<u32>| {
+        operator_histogram(expr)[Operator::Div.index()] > 0
+    };
+    let unlimited = search(SearchOptions::default());
+    assert!(unlimited.iter().any(uses_division));
+    let mut costs = [1; OPERATOR_COUNT];
+    costs[Operator::Div.index()] = 10;
+    let capped =
+        search(SearchOptions::default().operator_costs(costs).max_cost(5));
+    assert!(!capped.is_empty());
+    assert!(!capped.iter().any(uses_division));
+    let limit = CostLimit { costs, max_cost: 5 };
+    assert!(
+        capped
+            .iter()
+            .all(|expr| limit.cost(expr).is_some_and(|cost| cost <= 5))
+    );
+    assert_eq!(
+        capped.len(),
+        unlimited
+            .iter()
+            .filter(|expr| limit.cost(*expr).is_some_and(|cost| cost <= 5))
+            .count()
+    );
+}
+#[cfg(feature = "parallel")]
+#[test]
+fn test_generate_tree_parallel() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let generate = |numbers: &[u32], options: SearchOptions| {
+        let mut results = CachingTransciever::default();
+        generate_tree(
+            numbers,
+            &NormalNumberSystem,
+            &mut results,
+            &Operators::EVERY,
+            options,
+        );
+        let mut strings: Vec<String> =
+            results.0.iter().map(|e| e.to_string()).collect();
+        strings.sort();
+        strings
+    };
+    for numbers in [vec![7u32], vec![2, 3, 5, 7], vec![1, 3, 3, 8, 25]] {
+        let serial = generate(&numbers, SearchOptions::default());
+        let parallel =
+            generate(&numbers, SearchOptions::default().parallel(true));
+        assert!(!serial.is_empty());
+        assert_eq!(serial, parallel);
+    }
+    let parallel = SearchOptions::default().parallel(true);
+    assert!(generate(&[1, 2, 3], parallel.max_operations(1)).is_empty());
+    // Stopping early cancels the workers rather than waiting for them.
+    let parallel = SearchOptions::default().parallel(true);
+    let first = generate(&[1, 3, 3, 8, 25, 50], parallel.max_results(3));
+    assert_eq!(first.len(), 3);
+}
+#[test]
+fn test_generate_tree_subtrees() {
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    let operators = Operators::from_iter([Operator::Add, Operator::Mul]);
+    let numbers = [3u32, 2, 4];
+    let mut whole = CachingTransciever::default();
+    generate_tree(
+        &numbers,
+        &NormalNumberSystem,
+        &mut MySender::filter(&mut whole, |expr: &Expression<u32>| {
+            *expr.get_value() == 6
+        }),
+        &operators,
+        SearchOptions::default(),
+    );
+    assert!(whole.0.is_empty());
+    let mut subtrees = CachingTransciever::default();
+    generate_tree(
+        &numbers,
+        &NormalNumberSystem,
+        &mut MySender::filter(&mut subtrees, |expr: &Expression<u32>| {
+            *expr.get_value() == 6
+        }),
+        &operators,
+        SearchOptions::default().subtrees(true),
+    );
+    let found: Vec<String> =
+        subtrees.0.iter().map(|expr| expr.to_string()).collect();
+    assert_eq!(found, ["3 * 2"]);
+    // Every run is sent, shortest first, up to the maximum operations.
+    let mut runs = CachingTransciever::default();
+    generate_tree(
+        &numbers,
+        &NormalNumberSystem,
+        &mut runs,
+        &operators,
+        SearchOptions::default().subtrees(true).max_operations(1),
+    );
+    let runs: Vec<String> = runs.0.iter().map(|e| e.to_string()).collect();
+    assert_eq!(runs, ["3", "2", "4", "3 + 2", "3 * 2"]);
+}
+#[test]
+fn test_stored_values_match_re_eval() {
+    use crate::base_types::numbers::{ModularNumberSystem, NormalNumberSystem};
+    fn check_random_trees<N: NumberSystem<u32>>(
+        system: &N,
+        next: &mut impl FnMut() -> u32,
+    ) {
+        for _ in 0..8 {
+            let numbers: Vec<u32> = (0..4).map(|_| next()).collect();
+            let mut results = CachingTransciever::default();
+            generate_tree(
+                &numbers,
+                system,
+                &mut results,
+                &Operators::EVERY,
+                SearchOptions::default(),
+            );
+            assert!(!results.as_ref().is_empty(), "{numbers:?}");
+            for expr in results.as_ref() {
+                assert_eq!(expr.get_value(), &expr.re_eval(system), "{expr}");
+            }
+        }
+    }
+    // A small linear congruential generator keeps the trees reproducible.
+    let mut seed = 54321u32;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % 13 + 1
+    };
+    check_random_trees(&NormalNumberSystem, &mut next);
+    // The source numbers go up to 13, so not all of them are residues until
+    // the generator reduces them.
+    check_random_trees(&ModularNumberSystem::new(13), &mut next);
+    check_random_trees(&ModularNumberSystem::new(14), &mut next);
+}
+#[test]
+fn test_find_expressions_from_iterators() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let mut from_vec = CachingTransciever::default();
+    find_expressions(
+        vec![1u32, 2, 3, 4],
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        &mut from_vec,
+        SearchOptions::default(),
+    );
+    assert!(!from_vec.as_ref().is_empty());
+    let mut from_array = CachingTransciever::default();
+    find_expressions(
+        [1u32, 2, 3, 4],
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        &mut from_array,
+        SearchOptions::default(),
+    );
+    assert_eq!(from_array.as_ref(), from_vec.as_ref());
+    let mut from_range = CachingTransciever::default();
+    find_expressions(
+        1u32..=4,
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        &mut from_range,
+        SearchOptions::default(),
+    );
+    assert_eq!(from_range.as_ref(), from_vec.as_ref());
+}
+#[test]
+fn test_first_solution() {
+    use std::time::Instant;
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    let start = Instant::now();
+    // Every solution over eight numbers would take far longer to find.
+    let expr =
+        first_solution(1u32..=8, &NormalNumberSystem, 101, &Operators::ALL)
+            .unwrap();
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "{:?}",
+        start.elapsed()
+    );
+    assert_eq!(*expr.get_value(), 101);
+    assert_eq!(expr.re_eval(&NormalNumberSystem), 101);
+    let add =
+        Operators::from_iter([crate::base_types::expressions::Operator::Add]);
+    assert_eq!(
+        first_solution([1u32, 2], &NormalNumberSystem, 4, &add),
+        None
+    );
+}
+#[test]
+fn test_generate_tree_shared() {
+    use std::collections::HashSet;
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    /// Collects the address of every distinct node in the expression
+    fn shared_nodes<T: NumberType>(
+        expr: &Rc<RcExpression<T>>,
+        seen: &mut HashSet<*const RcExpression<T>>,
+    ) {
+        if !seen.insert(Rc::as_ptr(expr)) {
+            return;
+        }
+        if let RcExpression::Application(_, _, left, right) = expr.as_ref() {
+            shared_nodes(left, seen);
+            shared_nodes(right, seen);
+        }
+    }
+    let numbers = [1u32, 2, 3, 4, 5];
+    let mut owned = CachingTransciever::default();
+    generate_tree(
+        &numbers,
+        &NormalNumberSystem,
+        &mut owned,
+        &Operators::EVERY,
+        SearchOptions::default(),
+    );
+    let mut shared = CachingTransciever::default();
+    generate_tree_shared(
+        &numbers,
+        &NormalNumberSystem,
+        &mut shared,
+        &Operators::EVERY,
+        SearchOptions::default(),
+    );
+    assert!(!owned.as_ref().is_empty());
+    assert_eq!(owned.as_ref().len(), shared.as_ref().len());
+    for (owned, shared) in owned.as_ref().iter().zip(shared.as_ref()) {
+        assert_eq!(owned.to_string(), shared.to_string());
+        assert_eq!(owned, &shared.to_expression());
+    }
+    // Every owned tree allocates each of its nodes, while the shared trees
+    // only allocate the nodes which are not already shared.
+    let owned_nodes: usize =
+        owned.as_ref().iter().map(Expression::node_count).sum();
+    // The roots are kept alive while walking so no address is reused.
+    let roots: Vec<_> = shared.as_mut().drain(..).map(Rc::new).collect();
+    let mut seen = HashSet::new();
+    for root in &roots {
+        shared_nodes(root, &mut seen);
+    }
+    assert!(seen.len() >= roots.len());
+    assert!(
+        seen.len() * 4 < owned_nodes,
+        "{} shared nodes, {owned_nodes} owned nodes",
+        seen.len()
+    );
+}
+#[test]
+fn test_find_expressions_in_range() {
+    use std::collections::BTreeSet;
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    let mut results = CachingTransciever::default();
+    find_expressions_in_range(
+        [25u32, 50, 75, 100, 3],
+        &NormalNumberSystem,
+        900,
+        910,
+        &Operators::ALL,
+        &mut results,
+    );
+    let values: BTreeSet<u32> = results
+        .as_ref()
+        .iter()
+        .map(|expr| *expr.get_value())
+        .collect();
+    assert!(values.len() > 1, "{values:?}");
+    assert!(values.iter().all(|value| (900..=910).contains(value)));
+    for expr in results.as_ref() {
+        assert!(expr.check(&NormalNumberSystem), "{expr}");
+    }
+    // A wider range also reaches values which the narrow one leaves out.
+    let mut wider = CachingTransciever::default();
+    find_expressions_in_range(
+        [25u32, 50, 75, 100, 3],
+        &NormalNumberSystem,
+        850,
+        950,
+        &Operators::ALL,
+        &mut wider,
+    );
+    let (inside, outside): (Vec<_>, Vec<_>) = wider
+        .as_ref()
+        .iter()
+        .cloned()
+        .partition(|expr| (900..=910).contains(expr.get_value()));
+    assert!(!outside.is_empty());
+    assert_eq!(results.as_ref(), &inside);
+}
+#[test]
+fn test_no_redundant_right_nesting() {
+    use std::collections::HashSet;
+
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    /// Writes the expression fully parenthesised, with `a - (b + c)` and
+    /// `a / (b * c)` rewritten to their left-nested forms
+    fn form(expr: &Expression<u32>) -> String {
+        let Expression::Application(value, op, left, right) = expr else {
+            return expr.to_string();
+        };
+        match (op, right.as_ref()) {
+            | (
+                Operator::Sub,
+                Expression::Application(_, Operator::Add, b, c),
+            )
+            | (
+                Operator::Div,
+                Expression::Application(_, Operator::Mul, b, c),
+            ) => {
+                form(&Expression::Application(
+                    *value,
+                    *op,
+                    Box::new(Expression::Application(
+                        *value,
+                        *op,
+                        left.clone(),
+                        b.clone(),
+                    )),
+                    c.clone(),
+                ))
+            }
+            | _ => format!("({} {op} {})", form(left), form(right)),
+        }
+    }
+    let operators = Operators::from_iter([
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+    ]);
+    let mut seen = HashSet::new();
+    for numbers in SubsetPermutationGenerator::new(vec![24u32, 10, 4, 3, 2]) {
+        let mut results = CachingTransciever::default();
+        generate_tree(
+            &numbers,
+            &NormalNumberSystem,
+            &mut results,
+            &operators,
+            SearchOptions::default(),
+        );
+        for expr in results.as_ref() {
+            assert!(seen.insert((*expr.get_value(), form(expr))), "{expr}");
+        }
+    }
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        [10u32, 3, 2],
+        &NormalNumberSystem,
+        5,
+        &operators,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> = results
+        .as_ref()
+        .iter()
+        .map(|expr| expr.to_string())
+        .collect();
+    assert!(found.contains(&String::from("(10 - 3) - 2")), "{found:?}");
+    assert!(!found.contains(&String::from("10 - (3 + 2)")), "{found:?}");
+}
+#[test]
+fn test_find_expressions_with() {
+    use crate::base_types::numbers::NormalNumberSystem;
+    let mut all = Vec::new();
+    find_expressions_with(
+        [1u32, 2, 3, 4],
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        |expr| {
+            all.push(expr);
+            true
+        },
+    );
+    assert!(all.len() > 1);
+    let mut calls = 0;
+    find_expressions_with(
+        [1u32, 2, 3, 4],
+        &NormalNumberSystem,
+        10,
+        &Operators::ALL,
+        |expr| {
+            calls += 1;
+            assert_eq!(expr, all[0]);
+            false
+        },
+    );
+    assert_eq!(calls, 1);
+}
+#[test]
+fn test_bench_generate() {
+    use std::collections::BTreeSet;
+
+    use crate::base_types::numbers::NormalNumberSystem;
+    let numbers = [2u32, 3, 5, 7];
+    let count =
+        bench_generate(&numbers, &NormalNumberSystem, &Operators::EVERY);
+    let mut results = CachingTransciever::default();
+    generate_tree(
+        &numbers,
+        &NormalNumberSystem,
+        &mut results,
+        &Operators::EVERY,
+        SearchOptions::default(),
+    );
+    assert!(count > 0);
+    assert_eq!(count, results.as_ref().len());
+    // Over every ordering of every subset, counting each reachable value
+    // with count_expressions adds up to everything which was generated.
+    let numbers = [2u32, 3, 5];
+    let (mut generated, mut values) = (0, BTreeSet::new());
+    for permutation in SubsetPermutationGenerator::new(numbers) {
+        generated += bench_generate(
+            &permutation,
+            &NormalNumberSystem,
+            &Operators::EVERY,
+        );
+        let mut results = CachingTransciever::default();
+        generate_tree(
+            &permutation,
+            &NormalNumberSystem,
+            &mut results,
+            &Operators::EVERY,
+            SearchOptions::default(),
+        );
+        values.extend(results.as_ref().iter().map(|expr| *expr.get_value()));
+    }
+    let counted: u64 = values
+        .into_iter()
+        .map(|value| {
+            count_expressions(
+                numbers.to_vec(),
+                &NormalNumberSystem,
+                value,
+                &Operators::EVERY,
+            )
+        })
+        .sum();
+    assert_eq!(generated as u64, counted);
+}
+#[test]
+fn test_find_expressions_widened() {
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    let sources: [u8; 3] = [100, 50, 6];
+    let mut results = CachingTransciever::default();
+    find_expressions_widened(
+        sources,
+        &NormalNumberSystem,
+        5000u32,
+        &Operators::ALL,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> = results
+        .as_ref()
+        .iter()
+        .map(|expr| expr.to_string())
+        .collect();
+    assert!(found.contains(&String::from("100 * 50")), "{found:?}");
+    for expr in results.as_ref() {
+        assert!(expr.check(&NormalNumberSystem), "{expr}");
+    }
+    // Working in u8 itself, the product overflows instead.
+    assert_eq!(Operator::Mul.apply(&NormalNumberSystem, 100u8, 50), None);
+}
+#[test]
+fn test_max_cost_extremes() {
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    let search = |options: SearchOptions| {
+        let mut results = CachingTransciever::default();
+        find_expressions(
+            [1u32, 2, 3],
+            &NormalNumberSystem,
+            6,
+            &Operators::ALL,
+            &mut results,
+            options,
+        );
+        results.0
+    };
+    let mut costs = [1; OPERATOR_COUNT];
+    costs[Operator::Add.index()] = u32::MAX;
+    // A single addition costs exactly the budget, but two overflow it.
+    let found = search(
+        SearchOptions::default()
+            .operator_costs(costs)
+            .max_cost(u32::MAX),
+    );
+    let strings: Vec<String> = found.iter().map(|e| e.to_string()).collect();
+    assert!(strings.contains(&String::from("3 * 2")), "{strings:?}");
+    assert!(!strings.iter().any(|s| s.matches('+').count() > 1));
+    let limit = CostLimit {
+        costs,
+        max_cost: u32::MAX,
+    };
+    let sum = Expression::Application(
+        6,
+        Operator::Add,
+        Box::new(Expression::Application(
+            3u32,
+            Operator::Add,
+            Box::new(Expression::Value(2)),
+            Box::new(Expression::Value(1)),
+        )),
+        Box::new(Expression::Value(3)),
+    );
+    assert_eq!(limit.cost(&sum), None);
+    assert_eq!(limit.applied_cost(Some(u32::MAX), Operator::Mul), None);
+    assert_eq!(limit.applied_cost(Some(0), Operator::Add), Some(u32::MAX));
+}
+#[test]
+fn test_right_nesting_kept_without_left_nesting() {
+    use crate::base_types::{
+        expressions::Operator,
+        numbers::{ModularNumberSystem, NormalNumberSystem},
+    };
+    let basic = Operators::from_iter([
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+    ]);
+    // `(5 - 5) - 5` has a zero intermediate, so `5 - (5 + 5)` is the only
+    // way to reach -5.
+    let system = NormalNumberSystem::builder()
+        .allow_negative_results(true)
+        .allow_commutative_duplicates(true);
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        [5i32, 5, 5],
+        &system,
+        -5,
+        &basic,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> =
+        results.as_ref().iter().map(|e| e.to_string()).collect();
+    assert!(found.contains(&String::from("5 - (5 + 5)")), "{found:?}");
+    // Likewise `(1 - 1) - 1` in arithmetic modulo 7.
+    let mut results = CachingTransciever::default();
+    find_expressions(
+        [1u32, 1, 1],
+        &ModularNumberSystem::new(7),
+        6,
+        &basic,
+        &mut results,
+        SearchOptions::default(),
+    );
+    let found: Vec<String> =
+        results.as_ref().iter().map(|e| e.to_string()).collect();
+    assert!(found.contains(&String::from("1 - (1 + 1)")), "{found:?}");
+}
+#[test]
+fn test_generate_tree_options() {
+    use crate::base_types::{
+        expressions::Operator, numbers::NormalNumberSystem,
+    };
+    let generate = |options: SearchOptions| {
+        let mut results = CachingTransciever::default();
+        generate_tree(
+            &[12u32, 3, 2, 1],
+            &NormalNumberSystem,
+            &mut results,
+            &Operators::ALL,
+            options,
+        );
+        results.0
+    };
+    let unlimited = generate(SearchOptions::default());
+    let mut costs = [1; OPERATOR_COUNT];
+    costs[Operator::Div.index()] = 10;
+    let limit = CostLimit { costs, max_cost: 5 };
+    let capped =
+        generate(SearchOptions::default().operator_costs(costs).max_cost(5));
+    let affordable: Vec<_> = unlimited
+        .iter()
+        .filter(|expr| limit.cost(*expr).is_some_and(|cost| cost <= 5))
+        .cloned()
+        .collect();
+    assert!(!capped.is_empty() && capped.len() < unlimited.len());
+    assert_eq!(Vec::from(capped), affordable);
+    let first = generate(SearchOptions::default().max_results(2));
+    assert_eq!(first, unlimited.range(..2).cloned().collect::<Vec<_>>());
+    assert!(generate(SearchOptions::default().max_operations(2)).is_empty());
+}
@@ -2,4 +2,6 @@ pub mod permutation_generator;
 pub mod subset_generator;
 
 pub mod expression_tree_generator;
+pub mod progress;
+pub mod repeated_sequence_generator;
 pub mod subset_permutation_generator;
@@ -73,7 +73,7 @@ fn test_unique_generator() {
         permutation_count += 1;
     }
 
-    assert_eq!(permutation_count, (1..item_count).product())
+    assert_eq!(permutation_count, (1..item_count).product::<u64>())
 }
 
 #[derive(Debug)]
@@ -0,0 +1,155 @@
+//! # Progress
+//! Periodic reports on how far a search has got, so frontends can show
+//! progress without timing the search themselves.
+use std::time::{Duration, Instant};
+
+/// A snapshot of a running search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// The number of orderings of the source numbers which were searched
+    pub permutations_done: usize,
+    /// The number of matching expressions which were sent
+    pub expressions_emitted: usize,
+    /// The time since the search started
+    pub elapsed: Duration,
+}
+
+/// Calls a callback at most once per interval with a [`ProgressEvent`].
+pub struct Progress<'a> {
+    interval: Duration,
+    next: Duration,
+    clock: Box<dyn FnMut() -> Duration + 'a>,
+    callback: Box<dyn FnMut(ProgressEvent) + 'a>,
+}
+
+impl<'a> Progress<'a> {
+    /// Reports every `interval` of wall clock time, starting now.
+    pub fn new(
+        interval: Duration,
+        callback: impl FnMut(ProgressEvent) + 'a,
+    ) -> Self {
+        let start = Instant::now();
+        Self::with_clock(interval, move || start.elapsed(), callback)
+    }
+    /// Reports every `interval` as measured by `clock`, which gives the time
+    /// since the search started.
+    pub fn with_clock(
+        interval: Duration,
+        clock: impl FnMut() -> Duration + 'a,
+        callback: impl FnMut(ProgressEvent) + 'a,
+    ) -> Self {
+        Self {
+            interval,
+            next: interval,
+            clock: Box::new(clock),
+            callback: Box::new(callback),
+        }
+    }
+    /// Calls the callback if another interval boundary has passed. Several
+    /// boundaries passing at once only give a single event.
+    pub(crate) fn update(
+        &mut self,
+        permutations_done: usize,
+        expressions_emitted: usize,
+    ) {
+        let elapsed = (self.clock)();
+        if elapsed < self.next {
+            return;
+        }
+        (self.callback)(ProgressEvent {
+            permutations_done,
+            expressions_emitted,
+            elapsed,
+        });
+        if self.interval.is_zero() {
+            return;
+        }
+        while self.next <= elapsed {
+            self.next += self.interval;
+        }
+    }
+}
+/// An exponential moving average of how many expressions are emitted per
+/// second, fed with successive progress events.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressRate {
+    smoothing: f64,
+    last: Option<ProgressEvent>,
+    average: Option<f64>,
+}
+
+impl ProgressRate {
+    /// Creates an average where each new interval has weight `smoothing`,
+    /// which should be in `(0, 1]`. A weight of one only keeps the rate over
+    /// the last interval.
+    pub fn new(smoothing: f64) -> Self {
+        Self {
+            smoothing,
+            last: None,
+            average: None,
+        }
+    }
+    /// Adds the rate since the previous event (or the start of the search)
+    /// to the average and returns the new average. An event with no time
+    /// since the previous one leaves the average unchanged.
+    pub fn update(&mut self, event: ProgressEvent) -> f64 {
+        let (emitted, elapsed) = self.last.map_or((0, Duration::ZERO), |e| {
+            (e.expressions_emitted, e.elapsed)
+        });
+        self.last = Some(event);
+        let seconds = event.elapsed.saturating_sub(elapsed).as_secs_f64();
+        if seconds > 0.0 {
+            let found = event.expressions_emitted.saturating_sub(emitted);
+            let rate = found as f64 / seconds;
+            self.average = Some(match self.average {
+                | Some(average) => average + self.smoothing * (rate - average),
+                | None => rate,
+            });
+        }
+        self.average.unwrap_or(0.0)
+    }
+}
+
+#[test]
+fn test_progress_boundaries() {
+    let mut times = [0, 5, 10, 12, 25, 31, 39].into_iter();
+    let mut events = Vec::new();
+    let mut progress = Progress::with_clock(
+        Duration::from_millis(10),
+        || Duration::from_millis(times.next().unwrap()),
+        |event| events.push(event),
+    );
+    for i in 0..7 {
+        progress.update(i, i * 2);
+    }
+    drop(progress);
+    let elapsed: Vec<u128> =
+        events.iter().map(|e| e.elapsed.as_millis()).collect();
+    assert_eq!(elapsed, [10, 25, 31]);
+    assert_eq!(
+        events[1],
+        ProgressEvent {
+            permutations_done: 4,
+            expressions_emitted: 8,
+            elapsed: Duration::from_millis(25),
+        }
+    );
+}
+#[test]
+fn test_progress_rate() {
+    let event = |expressions_emitted, seconds| {
+        ProgressEvent {
+            permutations_done: 0,
+            expressions_emitted,
+            elapsed: Duration::from_secs(seconds),
+        }
+    };
+    let mut rate = ProgressRate::new(0.5);
+    // 100/s, then 300/s, then 0/s, then a repeated timestamp.
+    let rates: Vec<f64> = [(1000, 10), (4000, 20), (4000, 30), (4000, 30)]
+        .into_iter()
+        .map(|(emitted, seconds)| rate.update(event(emitted, seconds)))
+        .collect();
+    assert_eq!(rates, [100.0, 200.0, 100.0, 100.0]);
+    assert_eq!(ProgressRate::new(0.5).update(event(5, 0)), 0.0);
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeSet;
+
+use crate::base_types::numbers::NumberType;
+
+/// Generates every sequence of the distinct source numbers, with repetition,
+/// from length one up to a maximum length. With `n` distinct numbers there
+/// are `n + n^2 + ... + n^max_length` sequences, so the search grows
+/// exponentially in the maximum length.
+pub struct RepeatedSequenceGenerator<T: NumberType> {
+    values: Vec<T>,
+    indices: Vec<usize>,
+    max_length: usize,
+}
+impl<T: NumberType> RepeatedSequenceGenerator<T> {
+    pub fn new<E: IntoIterator<Item = T>>(t: E, max_length: usize) -> Self {
+        let values: Vec<T> =
+            t.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+        Self {
+            indices: Vec::new(),
+            max_length: if values.is_empty() { 0 } else { max_length },
+            values,
+        }
+    }
+}
+impl<T: NumberType> Iterator for RepeatedSequenceGenerator<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Advance the indices like an odometer, growing when it wraps.
+        let mut position = self.indices.len();
+        loop {
+            if position == 0 {
+                if self.indices.len() >= self.max_length {
+                    return None;
+                }
+                self.indices.iter_mut().for_each(|a| *a = 0);
+                self.indices.push(0);
+                break;
+            }
+            position -= 1;
+            self.indices[position] += 1;
+            if self.indices[position] < self.values.len() {
+                break;
+            }
+            self.indices[position] = 0;
+        }
+        Some(self.indices.iter().map(|a| self.values[*a]).collect())
+    }
+}
+
+#[test]
+fn test_repeated_sequence_generator() {
+    let sequences: Vec<Vec<u8>> =
+        RepeatedSequenceGenerator::new([1, 2, 2], 2).collect();
+    assert_eq!(
+        sequences,
+        [
+            vec![1],
+            vec![2],
+            vec![1, 1],
+            vec![1, 2],
+            vec![2, 1],
+            vec![2, 2]
+        ]
+    );
+    assert_eq!(
+        RepeatedSequenceGenerator::new([1u8, 2, 3], 3).count(),
+        3 + 9 + 27
+    );
+    assert_eq!(RepeatedSequenceGenerator::<u8>::new([], 3).count(), 0);
+}
@@ -9,14 +9,46 @@ pub struct SubsetPermutationGenerator<T: NumberType> {
 }
 impl<T: NumberType> SubsetPermutationGenerator<T> {
     pub fn new<E: IntoIterator<Item = T>>(t: E) -> Self {
-        let mut subsets = KVPairIterator::from(KeyCount::from_iter(t)).collect::<Vec<_>>();
-        subsets.sort_by_cached_key(|a| -(a.iter().map(|(_, a)| *a).sum::<usize>() as isize));
+        let mut subsets =
+            KVPairIterator::from(KeyCount::from_iter(t)).collect::<Vec<_>>();
+        subsets.sort_by_cached_key(|a| {
+            -(a.iter().map(|(_, a)| *a).sum::<usize>() as isize)
+        });
 
         Self {
             subsets,
             permutation_generator: None,
         }
     }
+    /// Drops every subset which leaves out some of the numbers, so only the
+    /// permutations of all of them are generated.
+    pub fn use_all(mut self) -> Self {
+        let size =
+            |a: &Vec<(T, usize)>| a.iter().map(|(_, a)| *a).sum::<usize>();
+        let full = self.subsets.iter().map(size).max().unwrap_or(0);
+        self.subsets.retain(|a| size(a) == full);
+        self
+    }
+    /// The number of permutations of the subsets which have not been started
+    /// yet, which is every permutation that will be yielded before iterating.
+    /// Each subset with counts `c_i` has `(sum c_i)! / prod(c_i!)` orderings.
+    pub fn total_count(&self) -> u128 {
+        self.subsets
+            .iter()
+            .map(|subset| {
+                let (mut count, mut n) = (1u128, 0u128);
+                for (_, c) in subset {
+                    // Multiplying in one binomial factor at a time keeps every
+                    // step a whole number.
+                    for j in 1..=*c as u128 {
+                        n += 1;
+                        count = count * n / j;
+                    }
+                }
+                count
+            })
+            .sum()
+    }
 }
 impl<T: NumberType> Iterator for SubsetPermutationGenerator<T> {
     type Item = Vec<T>;
@@ -24,7 +56,8 @@ impl<T: NumberType> Iterator for SubsetPermutationGenerator<T> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.permutation_generator.is_none() {
             if let Some(b) = self.subsets.pop() {
-                self.permutation_generator = Some(PermutationGenerator::from_iter(b));
+                self.permutation_generator =
+                    Some(PermutationGenerator::from_iter(b));
             } else {
                 return None;
             }
@@ -46,3 +79,37 @@ fn test() {
         println!("{i:?}");
     }
 }
+#[test]
+fn test_use_all() {
+    let permutations: Vec<Vec<u32>> =
+        SubsetPermutationGenerator::new([1, 2, 2])
+            .use_all()
+            .collect();
+    assert_eq!(permutations.len(), 3);
+    assert!(permutations.iter().all(|a| a.len() == 3));
+}
+#[test]
+fn test_total_count() {
+    for numbers in [
+        vec![1u32, 2, 3, 1],
+        vec![4, 4, 4],
+        vec![1, 2, 3, 4, 5],
+        vec![],
+    ] {
+        let generator = SubsetPermutationGenerator::new(numbers.clone());
+        let total = generator.total_count();
+        assert_eq!(total, generator.count() as u128, "{numbers:?}");
+    }
+    // Three orderings of one number, seven of two, twelve of three and twelve
+    // of all four.
+    assert_eq!(
+        SubsetPermutationGenerator::new([1u32, 2, 3, 1]).total_count(),
+        34
+    );
+    assert_eq!(
+        SubsetPermutationGenerator::new([1u32, 2, 3, 1])
+            .use_all()
+            .total_count(),
+        12
+    );
+}
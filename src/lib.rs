@@ -1,6 +1,13 @@
 #![warn(clippy::complexity)]
+//! Without the default `std` feature only the number and expression types in
+//! [`base_types`] are built, using `alloc` for their boxes and vectors.
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
 pub mod base_types;
+#[cfg(feature = "std")]
 pub mod generators;
 #[cfg(feature = "parsing")]
 pub mod parsing;
+pub mod presets;
+#[cfg(feature = "std")]
 pub mod timing;
@@ -2,7 +2,7 @@ use std::{collections::VecDeque, fmt::Debug, str::FromStr};
 
 use crate::base_types::numbers::NumberType;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Token {
     Number(String),
     Punctuation(char),
@@ -17,8 +17,37 @@ pub enum TokenType {
 }
 
 pub mod token_reader;
+pub mod words;
 pub trait Parsable: Sized {
+    /// Parses a value from the front of the tokens, leaving any tokens after
+    /// it for the caller.
     fn parse(tokens: &mut VecDeque<Token>) -> Result<Self, String>;
+    /// Parses a whole line, prefixing any error with the column of the token
+    /// which caused it. Tokens left over after the value are an error.
+    fn parse_line(line: &str) -> Result<Self, String> {
+        let spanned = token_reader::read_spanned(line)?;
+        let mut tokens: VecDeque<Token> =
+            spanned.iter().map(|(t, _)| t.clone()).collect();
+        let result = Self::parse(&mut tokens).map_err(|e| {
+            // The offending token is the last one which was consumed.
+            let consumed = spanned.len() - tokens.len();
+            let column = match consumed.checked_sub(1) {
+                | Some(i) => spanned[i].1,
+                | None => spanned.first().map_or(line.len(), |(_, p)| *p),
+            };
+            format!("at column {column}: {e}")
+        })?;
+        match tokens.front() {
+            | None => Ok(result),
+            | Some(token) => {
+                let column = spanned[spanned.len() - tokens.len()].1;
+                Err(format!(
+                    "at column {column}: Expected the end of the line found \
+                     {token:?}"
+                ))
+            }
+        }
+    }
 }
 impl<T: Parsable> Parsable for Vec<T> {
     fn parse(tokens: &mut VecDeque<Token>) -> Result<Self, String> {
@@ -38,13 +67,171 @@ impl<T: Parsable> Parsable for Vec<T> {
         Ok(result)
     }
 }
-impl<T: FromStr<Err: Debug> + Clone + Debug + NumberType> Parsable for T {
+/// Parses `digits` (with an optional leading `-`) in the given radix.
+///
+/// `NumberType` has no `from_str_radix`, so the value is built up from `ONE`
+/// with checked arithmetic, which returns `None` on a bad digit or overflow.
+fn from_str_radix<T: NumberType>(digits: &str, radix: u32) -> Option<T> {
+    let from_u32 =
+        |n: u32| (0..n).try_fold(T::ZERO, |total, _| total.checked_add(T::ONE));
+    let (negative, digits) = match digits.strip_prefix('-') {
+        | Some(digits) => (true, digits),
+        | None => (false, digits),
+    };
+    let base = from_u32(radix)?;
+    let mut value = T::ZERO;
+    for c in digits.chars() {
+        let digit = from_u32(c.to_digit(radix)?)?;
+        value = value.checked_mul(base)?.checked_add(digit)?;
+    }
+    match negative {
+        | true => T::ZERO.checked_sub(value),
+        | false => Some(value),
+    }
+}
+/// A number type which knows how to read its own literals, so it does not
+/// need a `FromStr` implementation to be [`Parsable`].
+pub trait ParsableNumber: NumberType {
+    /// Reads one number from the front of the tokens.
+    fn from_tokens(tokens: &mut VecDeque<Token>) -> Result<Self, String>;
+}
+impl<T: ParsableNumber> Parsable for T {
     fn parse(tokens: &mut VecDeque<Token>) -> Result<Self, String> {
-        match tokens.pop_front() {
-            | Some(Token::Number(t) | Token::Word(t)) => {
-                Ok(T::from_str(&t).map_err(|e| format!("{e:?}"))?)
+        T::from_tokens(tokens)
+    }
+}
+/// Reads a single number token using `FromStr`, or its radix for a `0x` or
+/// `0b` literal. This is how the primitive integers read their literals.
+pub fn from_str_tokens<T: FromStr<Err: Debug> + NumberType>(
+    tokens: &mut VecDeque<Token>,
+) -> Result<T, String> {
+    match tokens.pop_front() {
+        | Some(Token::Number(t) | Token::Word(t)) => {
+            match token_reader::split_radix(&t) {
+                | Some((radix, digits)) => {
+                    from_str_radix(&digits, radix).ok_or_else(|| {
+                        format!("Invalid base {radix} number {t:?}")
+                    })
+                }
+                | None => {
+                    T::from_str(&t).map_err(|e| {
+                        format!(
+                            "{t:?} is not a valid {}: {e:?}",
+                            std::any::type_name::<T>()
+                        )
+                    })
+                }
             }
-            | a => Err(format!("Expected number found {:?}", a)),
         }
+        | a => Err(format!("Expected number found {:?}", a)),
     }
 }
+macro_rules! impl_parsable_number {
+    ($t:ident) => {
+        impl ParsableNumber for $t {
+            fn from_tokens(
+                tokens: &mut VecDeque<Token>,
+            ) -> Result<Self, String> {
+                from_str_tokens(tokens)
+            }
+        }
+    };
+}
+impl_parsable_number!(u8);
+impl_parsable_number!(u16);
+impl_parsable_number!(u32);
+impl_parsable_number!(u64);
+impl_parsable_number!(u128);
+impl_parsable_number!(usize);
+impl_parsable_number!(i8);
+impl_parsable_number!(i16);
+impl_parsable_number!(i32);
+impl_parsable_number!(i64);
+impl_parsable_number!(i128);
+impl_parsable_number!(isize);
+
+#[test]
+fn test_error_column() {
+    assert_eq!(
+        Vec::<u32>::parse_line("[1, 2, +]"),
+        Err(String::from(
+            "at column 7: Expected number found Some(Punctuation('+'))"
+        ))
+    );
+    assert_eq!(
+        token_reader::read_spanned("[12, abc]").unwrap(),
+        [
+            (Token::Punctuation('['), 0),
+            (Token::Number(String::from("12")), 1),
+            (Token::Punctuation(','), 3),
+            (Token::Word(String::from("abc")), 5),
+            (Token::Punctuation(']'), 8),
+        ]
+    );
+    assert_eq!(Vec::<u32>::parse_line("[1, 2]"), Ok(vec![1, 2]));
+    assert_eq!(
+        Vec::<u32>::parse_line("[1, 2] 3"),
+        Err(String::from(
+            "at column 7: Expected the end of the line found \
+             Number(\"3\")"
+        ))
+    );
+}
+#[test]
+fn test_negative_numbers() {
+    assert_eq!(Vec::<i32>::parse_line("[-5, 3]"), Ok(vec![-5, 3]));
+    assert_eq!(Vec::<i32>::parse_line("[3,-5]"), Ok(vec![3, -5]));
+    assert_eq!(i32::parse_line("-12"), Ok(-12));
+    for line in ["[5 - 3]", "[5 -3]"] {
+        assert_eq!(
+            token_reader::read(line.to_string()).unwrap()[1..4],
+            [
+                Token::Number(String::from("5")),
+                Token::Punctuation('-'),
+                Token::Number(String::from("3")),
+            ]
+        );
+    }
+}
+#[test]
+fn test_decimal_numbers() {
+    let read = |line: &str| token_reader::read(line.to_string()).unwrap();
+    let number = |s: &str| Token::Number(String::from(s));
+    assert_eq!(read("1.5"), [number("1.5")]);
+    assert_eq!(read("-1.5"), [number("-1.5")]);
+    assert_eq!(read(".5"), [Token::Punctuation('.'), number("5")]);
+    assert_eq!(read("5."), [number("5"), Token::Punctuation('.')]);
+    assert_eq!(
+        read("1.2.3"),
+        [number("1.2"), Token::Punctuation('.'), number("3")]
+    );
+}
+#[test]
+fn test_radix_numbers() {
+    assert_eq!(Vec::<u32>::parse_line("[0xFF, 0b101]"), Ok(vec![255, 5]));
+    assert_eq!(
+        token_reader::read(String::from("[0x1f,0b10]")).unwrap(),
+        [
+            Token::Punctuation('['),
+            Token::Number(String::from("0x1f")),
+            Token::Punctuation(','),
+            Token::Number(String::from("0b10")),
+            Token::Punctuation(']'),
+        ]
+    );
+    assert_eq!(i32::parse_line("-0x10"), Ok(-16));
+    assert_eq!(Vec::<u32>::parse_line("[0, 10, 7]"), Ok(vec![0, 10, 7]));
+    assert!(u8::parse_line("0x100").is_err());
+    assert!(u32::parse_line("0b102").is_err());
+}
+#[test]
+fn test_out_of_range_number() {
+    assert_eq!(
+        u8::parse_line("300"),
+        Err(String::from(
+            "at column 0: \"300\" is not a valid u8: \
+             ParseIntError { kind: PosOverflow }"
+        ))
+    );
+    assert!(Vec::<u8>::parse_line("[255, 256]").is_err());
+}
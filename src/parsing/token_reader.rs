@@ -1,9 +1,29 @@
 use super::{Token, TokenType};
+/// Splits the line into tokens.
 pub fn read(line: String) -> Result<Vec<Token>, String> {
+    Ok(read_spanned(&line)?.into_iter().map(|(t, _)| t).collect())
+}
+/// Splits the line into tokens, along with the byte offset of the start of
+/// each token.
+///
+/// A `-` directly followed by a digit is read as the sign of a negative number
+/// when it starts the line or follows `[`, `,` or `(`. Anywhere else it is
+/// punctuation, so `[5 - 3]` and `[5 -3]` are both subtractions.
+///
+/// A single `.` with digits on both sides is kept inside the number, so `1.5`
+/// is one token. Any other `.` is punctuation: `.5` and `5.` split off the
+/// dot, and `1.2.3` reads as `1.2`, `.`, `3`.
+///
+/// A `0x` or `0b` prefix followed by a digit of that radix starts a hex or
+/// binary number, and every following digit of the radix (including the
+/// letters `a` to `f` of hex) is kept in the same token, so `0xFF` is one
+/// number.
+pub fn read_spanned(line: &str) -> Result<Vec<(Token, usize)>, String> {
     let mut tokens = Vec::new();
     let mut old_type = TokenType::None;
     let mut s = String::new();
-    for c in line.chars() {
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
         let c_type = match c {
             | 'A'..='Z' | 'a'..='z' => TokenType::Word,
             | '0'..='9' => TokenType::Number,
@@ -12,18 +32,55 @@ pub fn read(line: String) -> Result<Vec<Token>, String> {
             }
             | _ => TokenType::None,
         };
+        let next_is_digit =
+            |radix: u32| line[i + 1..].starts_with(|c: char| c.is_digit(radix));
+        let is_radix_prefix = old_type == TokenType::Number
+            && matches!(s.as_str(), "0" | "-0")
+            && radix_of_prefix(c).is_some_and(next_is_digit);
+        let is_radix_digit = old_type == TokenType::Number
+            && radix(&s).is_some_and(|radix| c.is_digit(radix));
+        if is_radix_prefix || is_radix_digit {
+            s.push(c);
+            continue;
+        }
+        let is_decimal_point = c == '.'
+            && old_type == TokenType::Number
+            && radix(&s).is_none()
+            && s.ends_with(|c: char| c.is_ascii_digit())
+            && !s.contains('.')
+            && line[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+        if is_decimal_point {
+            s.push(c);
+            continue;
+        }
         if old_type != c_type && !s.is_empty() {
             match old_type {
-                | TokenType::Number => tokens.push(Token::Number(s)),
+                | TokenType::Number => tokens.push((Token::Number(s), start)),
                 | TokenType::Punctuation => unreachable!(),
-                | TokenType::Word => tokens.push(Token::Word(s)),
+                | TokenType::Word => tokens.push((Token::Word(s), start)),
                 | TokenType::None => unreachable!(),
             }
             s = String::new();
         };
+        let is_sign = c == '-'
+            && s.is_empty()
+            && line[i + 1..].starts_with(|c: char| c.is_ascii_digit())
+            && matches!(
+                tokens.last(),
+                None | Some((Token::Punctuation('[' | ',' | '('), _))
+            );
+        if is_sign {
+            start = i;
+            s.push(c);
+            old_type = TokenType::Number;
+            continue;
+        }
         match c_type {
-            | TokenType::Punctuation => tokens.push(Token::Punctuation(c)),
+            | TokenType::Punctuation => tokens.push((Token::Punctuation(c), i)),
             | TokenType::Number | TokenType::Word => {
+                if s.is_empty() {
+                    start = i;
+                }
                 s.push(c);
             }
             | TokenType::None => {}
@@ -32,8 +89,8 @@ pub fn read(line: String) -> Result<Vec<Token>, String> {
     }
     if !s.is_empty() {
         match old_type {
-            | TokenType::Number => tokens.push(Token::Number(s)),
-            | TokenType::Word => tokens.push(Token::Word(s)),
+            | TokenType::Number => tokens.push((Token::Number(s), start)),
+            | TokenType::Word => tokens.push((Token::Word(s), start)),
             | TokenType::Punctuation => unreachable!(),
             | TokenType::None => unreachable!(),
         }
@@ -41,3 +98,30 @@ pub fn read(line: String) -> Result<Vec<Token>, String> {
 
     Ok(tokens)
 }
+/// The radix selected by the letter after a leading `0`.
+fn radix_of_prefix(c: char) -> Option<u32> {
+    match c {
+        | 'x' => Some(16),
+        | 'b' => Some(2),
+        | _ => None,
+    }
+}
+/// Splits a number token into its radix and its digits after the prefix,
+/// with the sign kept in front of the digits. Decimal tokens have no radix.
+pub(crate) fn split_radix(number: &str) -> Option<(u32, String)> {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        | Some(unsigned) => ("-", unsigned),
+        | None => ("", number),
+    };
+    let mut chars = unsigned.chars();
+    match (chars.next(), chars.next().and_then(radix_of_prefix)) {
+        | (Some('0'), Some(radix)) => {
+            Some((radix, format!("{sign}{}", chars.as_str())))
+        }
+        | _ => None,
+    }
+}
+/// The radix of a (possibly partial) number token.
+fn radix(number: &str) -> Option<u32> {
+    split_radix(number).map(|(radix, _)| radix)
+}
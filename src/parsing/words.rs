@@ -0,0 +1,209 @@
+//! # Number Words
+//! Reads lines such as `six plus four times three`, written out in English
+//! words, into the same tokens as `6 + 4 * 3`.
+use super::{Token, token_reader};
+
+/// The value of a word which adds to the number being built
+fn small_number(word: &str) -> Option<u64> {
+    let value = match word {
+        | "zero" => 0,
+        | "one" => 1,
+        | "two" => 2,
+        | "three" => 3,
+        | "four" => 4,
+        | "five" => 5,
+        | "six" => 6,
+        | "seven" => 7,
+        | "eight" => 8,
+        | "nine" => 9,
+        | "ten" => 10,
+        | "eleven" => 11,
+        | "twelve" => 12,
+        | "thirteen" => 13,
+        | "fourteen" => 14,
+        | "fifteen" => 15,
+        | "sixteen" => 16,
+        | "seventeen" => 17,
+        | "eighteen" => 18,
+        | "nineteen" => 19,
+        | "twenty" => 20,
+        | "thirty" => 30,
+        | "forty" => 40,
+        | "fifty" => 50,
+        | "sixty" => 60,
+        | "seventy" => 70,
+        | "eighty" => 80,
+        | "ninety" => 90,
+        | _ => return None,
+    };
+    Some(value)
+}
+/// The value of a word which multiplies the number being built
+fn scale(word: &str) -> Option<u64> {
+    match word {
+        | "hundred" => Some(100),
+        | "thousand" => Some(1_000),
+        | "million" => Some(1_000_000),
+        | _ => None,
+    }
+}
+/// The symbol of an operator word, along with the word which must follow it
+fn operator(word: &str) -> Option<(char, Option<&'static str>)> {
+    match word {
+        | "plus" => Some(('+', None)),
+        | "minus" => Some(('-', None)),
+        | "times" => Some(('*', None)),
+        | "multiplied" => Some(('*', Some("by"))),
+        | "divided" => Some(('/', Some("by"))),
+        | "mod" | "modulo" => Some(('%', None)),
+        | _ => None,
+    }
+}
+
+/// Splits the line into tokens like [`token_reader::read`], replacing number
+/// words with [`Token::Number`] and operator words with the matching
+/// [`Token::Punctuation`].
+///
+/// Consecutive number words make up a single number, so `three hundred and
+/// five` is read as `305`. A word only joins the number before it as a
+/// larger scale word or as the units after a tens word, so `six four` is an
+/// error rather than `10`. Digits and punctuation are kept as they are, and
+/// any other word is an error.
+pub fn read(line: &str) -> Result<Vec<Token>, String> {
+    let mut words = token_reader::read(line.to_lowercase())?.into_iter();
+    let mut result = Vec::new();
+    // The total of the completed scales, the part below the current one, and
+    // the last small number word of that part, if it ended with one.
+    let mut number: Option<(u64, u64, Option<u64>)> = None;
+    while let Some(token) = words.next() {
+        let word = match token {
+            | Token::Word(word) => word,
+            | token => {
+                finish(&mut number, &mut result);
+                result.push(token);
+                continue;
+            }
+        };
+        if let Some(value) = small_number(&word) {
+            let (_, part, last) = number.get_or_insert((0, 0, None));
+            match *last {
+                // Only units may follow a tens word, as in `twenty one`.
+                | Some(tens)
+                    if tens >= 20
+                        && tens % 10 == 0
+                        && (1..10).contains(&value) => {}
+                | Some(previous) => {
+                    return Err(format!(
+                        "Expected a scale word between {previous} and {word:?}"
+                    ));
+                }
+                | None => {}
+            }
+            *part += value;
+            *last = Some(value);
+        } else if let Some(value) = scale(&word) {
+            let Some((total, part, last)) = number.as_mut() else {
+                return Err(format!("Expected a number before {word:?}"));
+            };
+            // A scale must be larger than the scales before it, and hundreds
+            // must be counted in small numbers.
+            let larger = match value {
+                | 100 => last.is_some() && *part < 100,
+                | _ => *part > 0 && *total % (value * 1_000) == 0,
+            };
+            if !larger {
+                return Err(format!(
+                    "Unexpected {word:?} after {}",
+                    *total + *part
+                ));
+            }
+            match value {
+                | 100 => *part *= value,
+                | _ => {
+                    *total += *part * value;
+                    *part = 0;
+                }
+            }
+            *last = None;
+        } else if word == "and"
+            && let Some((_, _, last)) = number
+        {
+            // `and` only follows a scale, as in `three hundred and five`.
+            if let Some(previous) = last {
+                return Err(format!(
+                    "Expected a scale word before \"and\" after {previous}"
+                ));
+            }
+            continue;
+        } else if let Some((symbol, follower)) = operator(&word) {
+            finish(&mut number, &mut result);
+            if let Some(follower) = follower {
+                match words.next() {
+                    | Some(Token::Word(next)) if next == follower => {}
+                    | next => {
+                        return Err(format!(
+                            "Expected {follower:?} after {word:?} found \
+                             {next:?}"
+                        ));
+                    }
+                }
+            }
+            result.push(Token::Punctuation(symbol));
+        } else {
+            return Err(format!("Unknown word {word:?}"));
+        }
+    }
+    finish(&mut number, &mut result);
+    Ok(result)
+}
+/// Pushes the number being built, if there is one
+fn finish(
+    number: &mut Option<(u64, u64, Option<u64>)>,
+    result: &mut Vec<Token>,
+) {
+    if let Some((total, part, _)) = number.take() {
+        result.push(Token::Number((total + part).to_string()));
+    }
+}
+
+#[test]
+fn test_number_words() {
+    use crate::{base_types::expressions::Expression, parsing::Parsable};
+    let symbols = |line: &str| token_reader::read(line.to_string()).unwrap();
+    assert_eq!(read("six plus four"), Ok(symbols("6 + 4")));
+    assert_eq!(
+        read("Twenty one times three hundred and five"),
+        Ok(symbols("21 * 305"))
+    );
+    assert_eq!(
+        read("two thousand divided by (ten minus 2)"),
+        Ok(symbols("2000 / (10 - 2)"))
+    );
+    let mut tokens = read("four times three plus six").unwrap().into();
+    let expr = Expression::<u32>::parse(&mut tokens);
+    assert_eq!(expr.map(|e| *e.get_value()), Ok(18));
+    assert_eq!(
+        read("six plus apples"),
+        Err(String::from("Unknown word \"apples\""))
+    );
+    assert!(read("six divided four").is_err());
+}
+#[test]
+fn test_juxtaposed_number_words() {
+    let symbols = |line: &str| token_reader::read(line.to_string()).unwrap();
+    assert_eq!(
+        read("two million three thousand four hundred and fifty six"),
+        Ok(symbols("2003456"))
+    );
+    assert_eq!(read("two thousand and five"), Ok(symbols("2005")));
+    assert_eq!(
+        read("six four"),
+        Err(String::from("Expected a scale word between 6 and \"four\""))
+    );
+    assert!(read("six and four").is_err());
+    assert!(read("ten five").is_err());
+    assert!(read("twenty one two").is_err());
+    assert!(read("three hundred hundred").is_err());
+    assert!(read("two thousand three million").is_err());
+    assert!(read("two thousand thousand").is_err());
+}
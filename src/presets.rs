@@ -0,0 +1,49 @@
+//! # Presets
+//! Ready-made pairs of operators and number systems for common rule sets, so
+//! a search can be set up without knowing which flags model which game.
+use crate::base_types::{
+    expressions::{Operator, Operators},
+    numbers::{ModularNumberSystem, NormalNumberSystem, NumberType},
+};
+
+/// The four operators of basic arithmetic
+fn arithmetic() -> Operators {
+    Operators::from_iter([
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+    ])
+}
+/// The rules of the numbers round of the TV show Countdown: addition,
+/// subtraction, multiplication and division, where every division must be
+/// exact and every intermediate result must be a positive whole number.
+pub fn classic_countdown() -> (Operators, NormalNumberSystem) {
+    (arithmetic(), NormalNumberSystem)
+}
+/// The four operators of basic arithmetic modulo `p`. Division is only
+/// possible when `p` is prime, and is rejected by the system otherwise.
+pub fn modular<T: NumberType>(p: T) -> (Operators, ModularNumberSystem<T>) {
+    (arithmetic(), ModularNumberSystem::new(p))
+}
+
+#[test]
+fn test_classic_countdown() {
+    use crate::base_types::numbers::NumberSystem;
+    let (operators, system) = classic_countdown();
+    assert_eq!(operators, Operators::from_chars("+-*/").unwrap());
+    assert_eq!(system.div(8u32, 2), Some(4));
+    assert_eq!(system.div(7u32, 2), None);
+    assert_eq!(system.sub(5u32, 2), Some(3));
+    assert_eq!(system.sub(2i32, 5), None);
+    assert_eq!(system.sub(5i32, 5), None);
+}
+#[test]
+fn test_modular() {
+    use crate::base_types::numbers::NumberSystem;
+    let (operators, system) = modular(7u32);
+    assert_eq!(operators, classic_countdown().0);
+    assert_eq!(system.sub(2, 5), Some(4));
+    assert_eq!(system.div(3, 2), Some(5));
+    assert_eq!(modular(8u32).1.div(4, 2), None);
+}
@@ -0,0 +1,60 @@
+use std::mem;
+
+use super::MySender;
+
+/// A sender which buffers items and forwards them in batches of a fixed size.
+/// The last partial batch is forwarded when the sender is marked as done.
+pub struct BatchSender<'a, S: MySender<Vec<T>>, T> {
+    sender: &'a mut S,
+    batch: Vec<T>,
+    size: usize,
+}
+
+impl<'a, S: MySender<Vec<T>>, T> BatchSender<'a, S, T> {
+    /// # Panics
+    /// Panics if `size` is zero.
+    pub fn new(sender: &'a mut S, size: usize) -> Self {
+        assert!(size > 0, "batch size must be positive");
+        Self {
+            sender,
+            batch: Vec::with_capacity(size),
+            size,
+        }
+    }
+    fn flush(&mut self) -> bool {
+        let batch =
+            mem::replace(&mut self.batch, Vec::with_capacity(self.size));
+        self.sender.send(batch)
+    }
+}
+impl<'a, S: MySender<Vec<T>>, T> MySender<T> for BatchSender<'a, S, T> {
+    fn send(&mut self, value: T) -> bool {
+        self.batch.push(value);
+        if self.batch.len() >= self.size {
+            self.flush()
+        } else {
+            true
+        }
+    }
+
+    fn set_done(&mut self) {
+        if !self.batch.is_empty() {
+            self.flush();
+        }
+        self.sender.set_done();
+    }
+}
+
+#[test]
+fn test_batches() {
+    use super::{MyReciever, caching::CachingTransciever};
+    let mut batches = CachingTransciever::default();
+    let mut sender = batches.batched(4);
+    for i in 0..10 {
+        sender.send(i);
+    }
+    sender.set_done();
+    let sizes: Vec<usize> =
+        batches.into_iterator().map(|batch| batch.len()).collect();
+    assert_eq!(sizes, [4, 4, 2]);
+}
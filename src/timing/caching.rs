@@ -40,3 +40,25 @@ impl<T> MyReciever<T> for CachingTransciever<T> {
         self.0.is_empty() && self.1
     }
 }
+
+#[test]
+fn test_fold() {
+    let mut cache = CachingTransciever::default();
+    for i in 1..=4 {
+        cache.send(i);
+    }
+    cache.set_done();
+    assert_eq!(cache.fold(0, |a, b| a + b), 10);
+}
+#[test]
+fn test_collect_vec() {
+    let mut cache = CachingTransciever::default();
+    for i in [3, 1, 2] {
+        cache.send(i);
+    }
+    cache.set_done();
+    assert_eq!(cache.collect_vec(), [3, 1, 2]);
+    let mut empty = CachingTransciever::<u32>::default();
+    empty.set_done();
+    assert!(empty.collect_vec().is_empty());
+}
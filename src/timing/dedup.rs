@@ -0,0 +1,57 @@
+use std::{collections::HashSet, hash::Hash, marker::PhantomData};
+
+use super::MySender;
+
+/// A sender which only forwards the first item seen for each key.
+pub struct DedupTransciever<T, K, S: MySender<T>, F: FnMut(&T) -> K> {
+    sender: S,
+    seen: HashSet<K>,
+    key: F,
+    p: PhantomData<T>,
+}
+
+impl<T, K: Hash + Eq, S: MySender<T>, F: FnMut(&T) -> K>
+    DedupTransciever<T, K, S, F>
+{
+    /// Wraps the sender, extracting the key of every item with `key`.
+    pub fn new(sender: S, key: F) -> Self {
+        Self {
+            sender,
+            seen: HashSet::new(),
+            key,
+            p: PhantomData,
+        }
+    }
+    /// Unwraps the inner sender.
+    pub fn into_inner(self) -> S {
+        self.sender
+    }
+}
+impl<T, K: Hash + Eq, S: MySender<T>, F: FnMut(&T) -> K> MySender<T>
+    for DedupTransciever<T, K, S, F>
+{
+    fn send(&mut self, value: T) -> bool {
+        if self.seen.insert((self.key)(&value)) {
+            self.sender.send(value)
+        } else {
+            true
+        }
+    }
+
+    fn set_done(&mut self) {
+        self.sender.set_done();
+    }
+}
+
+#[test]
+fn test_dedup() {
+    use super::{MyReciever, caching::CachingTransciever};
+    let mut dedup =
+        DedupTransciever::new(CachingTransciever::default(), |a: &u32| a % 5);
+    for value in [1, 6, 2, 1, 7, 3, 11, 5] {
+        dedup.send(value);
+    }
+    dedup.set_done();
+    let results: Vec<u32> = dedup.into_inner().into_iterator().collect();
+    assert_eq!(results, [1, 2, 3, 5]);
+}
@@ -0,0 +1,81 @@
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use super::MyReciever;
+
+/// How far along a receiver was when an item arrived
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceiveStats {
+    /// How many items have arrived, including this one
+    pub count: usize,
+    /// The time since the receiver was enumerated
+    pub elapsed: Duration,
+}
+
+/// A receiver which pairs every item of the underlying receiver with the
+/// running count and the time since it was created, so consumers do not have
+/// to keep their own counters.
+pub struct EnumeratedReceiver<T, R: MyReciever<T>> {
+    receiver: R,
+    count: usize,
+    start: Instant,
+    p: PhantomData<T>,
+}
+
+impl<T, R: MyReciever<T>> EnumeratedReceiver<T, R> {
+    pub fn new(receiver: R) -> Self {
+        Self {
+            receiver,
+            count: 0,
+            start: Instant::now(),
+            p: PhantomData,
+        }
+    }
+    /// Pairs an item which arrived with the stats.
+    fn stats(&mut self, item: T) -> (T, ReceiveStats) {
+        self.count += 1;
+        let stats = ReceiveStats {
+            count: self.count,
+            elapsed: self.start.elapsed(),
+        };
+        (item, stats)
+    }
+}
+impl<T, R: MyReciever<T>> MyReciever<(T, ReceiveStats)>
+    for EnumeratedReceiver<T, R>
+{
+    fn receive(&mut self) -> Option<(T, ReceiveStats)> {
+        let item = self.receiver.receive()?;
+        Some(self.stats(item))
+    }
+
+    fn receive_blocking(&mut self) -> Option<(T, ReceiveStats)> {
+        let item = self.receiver.receive_blocking()?;
+        Some(self.stats(item))
+    }
+
+    fn isdone(&self) -> bool {
+        self.receiver.isdone()
+    }
+}
+
+#[test]
+fn test_enumerate() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut source = CachingTransciever::default();
+    for i in 0..5 {
+        source.send(i);
+    }
+    source.set_done();
+    let items = source.enumerate().collect_vec();
+    assert_eq!(
+        items.iter().map(|(item, _)| *item).collect::<Vec<i32>>(),
+        [0, 1, 2, 3, 4]
+    );
+    for (i, (_, stats)) in items.iter().enumerate() {
+        assert_eq!(stats.count, i + 1);
+    }
+    assert!(items.windows(2).all(|w| w[0].1.elapsed <= w[1].1.elapsed));
+}
@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+
+use super::{MyReciever, MySender};
+
+/// A sender which calls `func` on every item before forwarding it unchanged.
+pub struct SenderInspect<'a, S: MySender<T>, T, F> {
+    sender: &'a mut S,
+    func: F,
+    p: PhantomData<T>,
+}
+
+impl<'a, S: MySender<T>, T, F: FnMut(&T)> SenderInspect<'a, S, T, F> {
+    pub fn new(sender: &'a mut S, func: F) -> Self {
+        Self {
+            sender,
+            func,
+            p: PhantomData,
+        }
+    }
+}
+impl<'a, S: MySender<T>, T, F: FnMut(&T)> MySender<T>
+    for SenderInspect<'a, S, T, F>
+{
+    fn send(&mut self, value: T) -> bool {
+        (self.func)(&value);
+        self.sender.send(value)
+    }
+
+    fn set_done(&mut self) {
+        self.sender.set_done();
+    }
+}
+
+/// A receiver which calls `func` on every item before passing it on
+/// unchanged.
+pub struct ReceiverInspect<'a, R: MyReciever<T>, T, F> {
+    receiver: &'a mut R,
+    func: F,
+    p: PhantomData<T>,
+}
+
+impl<'a, R: MyReciever<T>, T, F: FnMut(&T)> ReceiverInspect<'a, R, T, F> {
+    pub fn new(receiver: &'a mut R, func: F) -> Self {
+        Self {
+            receiver,
+            func,
+            p: PhantomData,
+        }
+    }
+}
+impl<'a, R: MyReciever<T>, T, F: FnMut(&T)> MyReciever<T>
+    for ReceiverInspect<'a, R, T, F>
+{
+    fn receive(&mut self) -> Option<T> {
+        self.receiver.receive().inspect(&mut self.func)
+    }
+
+    fn receive_blocking(&mut self) -> Option<T> {
+        self.receiver.receive_blocking().inspect(&mut self.func)
+    }
+
+    fn isdone(&self) -> bool {
+        self.receiver.isdone()
+    }
+}
+
+#[test]
+fn test_sender_inspect() {
+    use super::caching::CachingTransciever;
+    let mut cache = CachingTransciever::default();
+    let mut seen = Vec::new();
+    let mut inspect = MySender::inspect(&mut cache, |a: &u32| seen.push(*a));
+    for i in [4, 2, 7] {
+        inspect.send(i);
+    }
+    inspect.set_done();
+    assert_eq!(seen, [4, 2, 7]);
+    assert_eq!(cache.collect_vec(), [4, 2, 7]);
+}
+#[test]
+fn test_receiver_inspect() {
+    use super::caching::CachingTransciever;
+    let mut cache = CachingTransciever::default();
+    for i in [4, 2, 7] {
+        cache.send(i);
+    }
+    cache.set_done();
+    let mut calls = 0;
+    let received = MyReciever::inspect(&mut cache, |_: &u32| calls += 1)
+        .into_iterator()
+        .collect::<Vec<_>>();
+    assert_eq!(received, [4, 2, 7]);
+    assert_eq!(calls, 3);
+}
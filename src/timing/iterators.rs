@@ -42,13 +42,15 @@ impl<T, R: MyReciever<T>> Iterator for ReceiverToIterator<T, R> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
+        // Receiving first means an item which arrived just before the
+        // receiver became done is still yielded.
         loop {
+            if let Some(t) = self.receiver.receive_blocking() {
+                break Some(t);
+            }
             if self.receiver.isdone() {
                 break None;
             }
-            if let Some(t) = self.receiver.receive() {
-                break Some(t);
-            }
         }
     }
 }
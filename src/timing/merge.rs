@@ -0,0 +1,59 @@
+use super::MyReciever;
+
+/// A receiver which takes turns receiving from each of its sources, so none
+/// of them is starved. It is done once every source is done.
+pub struct MergeReceiver<T> {
+    sources: Vec<Box<dyn MyReciever<T>>>,
+    next: usize,
+}
+
+impl<T> MergeReceiver<T> {
+    pub fn new(sources: Vec<Box<dyn MyReciever<T>>>) -> Self {
+        Self { sources, next: 0 }
+    }
+    /// Adds another source to take turns with.
+    pub fn push(&mut self, source: Box<dyn MyReciever<T>>) {
+        self.sources.push(source);
+    }
+}
+impl<T> MyReciever<T> for MergeReceiver<T> {
+    fn receive(&mut self) -> Option<T> {
+        let len = self.sources.len();
+        for offset in 0..len {
+            let i = (self.next + offset) % len;
+            if self.sources[i].isdone() {
+                continue;
+            }
+            if let Some(t) = self.sources[i].receive() {
+                // The next search starts after the source which just gave
+                // an item.
+                self.next = (i + 1) % len;
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    fn isdone(&self) -> bool {
+        self.sources.iter().all(|source| source.isdone())
+    }
+}
+
+#[test]
+fn test_merge() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut one = CachingTransciever::default();
+    let mut other = CachingTransciever::default();
+    for i in 0..3 {
+        one.send(i);
+    }
+    for i in 10..15 {
+        other.send(i);
+    }
+    one.set_done();
+    other.set_done();
+    let merged = MergeReceiver::new(vec![Box::new(one), Box::new(other)]);
+    let received = merged.collect_vec();
+    // The sources alternate until the shorter one runs out.
+    assert_eq!(received, [0, 10, 1, 11, 2, 12, 13, 14]);
+}
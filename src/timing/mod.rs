@@ -1,8 +1,14 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
+use batch::BatchSender;
+use enumerate::EnumeratedReceiver;
 use filter::{ReceiverFilter, SenderFilter};
+use inspect::{ReceiverInspect, SenderInspect};
 use iterators::ReceiverToIterator;
 use map::{ReceiverMap, SenderMap};
+use skip::SkipReceiver;
+use take::TakeReceiver;
+use throttle::ThrottleReceiver;
 
 pub trait MySender<T>: Sized {
     fn send(&mut self, value: T) -> bool;
@@ -13,9 +19,18 @@ pub trait MySender<T>: Sized {
     fn filter<'a, F: FnMut(&T) -> bool>(&'a mut self, func: F) -> SenderFilter<'a, Self, T, F> {
         SenderFilter::new(self, func)
     }
+    fn inspect<'a, F: FnMut(&T)>(&'a mut self, func: F) -> SenderInspect<'a, Self, T, F> {
+        SenderInspect::new(self, func)
+    }
     fn blocked<'a>(&'a mut self) -> BlockedSender<'a, Self, T> {
         BlockedSender::new(self)
     }
+    fn batched<'a, U>(&'a mut self, n: usize) -> BatchSender<'a, Self, U>
+    where
+        Self: MySender<Vec<U>>,
+    {
+        BatchSender::new(self, n)
+    }
 }
 pub struct BlockedSender<'a, S: MySender<T>, T>(&'a mut S, PhantomData<T>);
 
@@ -33,18 +48,90 @@ impl<'a, S: MySender<T>, T> BlockedSender<'a, S, T> {
     }
 }
 
-pub trait MyReciever<T>: Sized {
+pub trait MyReciever<T> {
     fn receive(&mut self) -> Option<T>;
+    /// Waits until an item arrives or the receiver is done, rather than timing
+    /// out. Defaults to a single `receive`.
+    fn receive_blocking(&mut self) -> Option<T> {
+        self.receive()
+    }
     fn isdone(&self) -> bool;
-    fn map<'a, S, F: FnMut(T) -> S>(&'a mut self, func: F) -> ReceiverMap<'a, Self, T, F> {
+    fn map<'a, S, F: FnMut(T) -> S>(&'a mut self, func: F) -> ReceiverMap<'a, Self, T, F>
+    where
+        Self: Sized,
+    {
         ReceiverMap::new(self, func)
     }
-    fn filter<'a, F: FnMut(&T) -> bool>(&'a mut self, func: F) -> ReceiverFilter<'a, Self, T, F> {
+    fn filter<'a, F: FnMut(&T) -> bool>(&'a mut self, func: F) -> ReceiverFilter<'a, Self, T, F>
+    where
+        Self: Sized,
+    {
         ReceiverFilter::new(self, func)
     }
-    fn into_iterator(self) -> ReceiverToIterator<T, Self> {
+    fn inspect<'a, F: FnMut(&T)>(&'a mut self, func: F) -> ReceiverInspect<'a, Self, T, F>
+    where
+        Self: Sized,
+    {
+        ReceiverInspect::new(self, func)
+    }
+    fn into_iterator(self) -> ReceiverToIterator<T, Self>
+    where
+        Self: Sized,
+    {
         ReceiverToIterator::new(self)
     }
+    fn take(self, n: usize) -> TakeReceiver<T, Self>
+    where
+        Self: Sized,
+    {
+        TakeReceiver::new(self, n)
+    }
+    /// Discards the first `n` items which arrive and then passes on the rest.
+    fn skip(self, n: usize) -> SkipReceiver<T, Self>
+    where
+        Self: Sized,
+    {
+        SkipReceiver::new(self, n)
+    }
+    /// Pairs every item with how many have arrived and the time taken.
+    fn enumerate(self) -> EnumeratedReceiver<T, Self>
+    where
+        Self: Sized,
+    {
+        EnumeratedReceiver::new(self)
+    }
+    /// Delivers the items at least `interval` apart, sleeping as needed.
+    fn throttle(self, interval: Duration) -> ThrottleReceiver<T, Self>
+    where
+        Self: Sized,
+    {
+        ThrottleReceiver::new(self, interval)
+    }
+    /// Drains the receiver until it is done, combining every item with `f`.
+    fn fold<B, F: FnMut(B, T) -> B>(self, init: B, f: F) -> B
+    where
+        Self: Sized,
+    {
+        self.into_iterator().fold(init, f)
+    }
+    /// Drains the receiver until it is done, collecting the items in order.
+    fn collect_vec(self) -> Vec<T>
+    where
+        Self: Sized,
+    {
+        self.into_iterator().collect()
+    }
+}
+impl<T, R: MyReciever<T> + ?Sized> MyReciever<T> for Box<R> {
+    fn receive(&mut self) -> Option<T> {
+        (**self).receive()
+    }
+    fn receive_blocking(&mut self) -> Option<T> {
+        (**self).receive_blocking()
+    }
+    fn isdone(&self) -> bool {
+        (**self).isdone()
+    }
 }
 
 pub mod threaded;
@@ -101,6 +188,10 @@ pub mod map {
             self.receiver.receive().map(|a| ((self.func)(a)))
         }
 
+        fn receive_blocking(&mut self) -> Option<U> {
+            self.receiver.receive_blocking().map(|a| (self.func)(a))
+        }
+
         fn isdone(&self) -> bool {
             self.receiver.isdone()
         }
@@ -160,6 +251,10 @@ pub mod filter {
             self.receiver.receive().filter(&mut self.func)
         }
 
+        fn receive_blocking(&mut self) -> Option<T> {
+            self.receiver.receive_blocking().filter(&mut self.func)
+        }
+
         fn isdone(&self) -> bool {
             self.receiver.isdone()
         }
@@ -168,17 +263,45 @@ pub mod filter {
 pub mod caching_async {
     use std::{
         collections::VecDeque,
-        sync::{Arc, Mutex},
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
     };
 
     use super::{MyReciever, MySender};
 
+    /// A queue shared between clones, which are all done once any of them
+    /// is set as done.
     #[derive(Clone)]
-    pub struct ConcurrentCachingTransiever<T>(pub Arc<Mutex<VecDeque<T>>>, bool);
+    pub struct ConcurrentCachingTransiever<T>(
+        pub Arc<Mutex<VecDeque<T>>>,
+        Arc<AtomicBool>,
+    );
 
     impl<T> Default for ConcurrentCachingTransiever<T> {
         fn default() -> Self {
-            Self(Default::default(), false)
+            Self(Default::default(), Default::default())
+        }
+    }
+
+    impl<T> ConcurrentCachingTransiever<T> {
+        /// Takes every queued item at once, locking only a single time
+        /// rather than once per item.
+        pub fn drain(&self) -> Vec<T> {
+            self.drain_done().0
+        }
+        /// Takes every queued item like [`drain`](Self::drain), along with
+        /// whether the senders are done. The flag is read under the same
+        /// lock as the drain, so no item can slip in between the two.
+        pub fn drain_done(&self) -> (Vec<T>, bool) {
+            match self.0.lock() {
+                Ok(mut o) => {
+                    let done = self.1.load(Ordering::Acquire);
+                    (std::mem::take(&mut *o).into(), done)
+                }
+                Err(e) => panic!("{:?}", e),
+            }
         }
     }
 
@@ -193,8 +316,11 @@ pub mod caching_async {
             }
         }
 
+        /// Sets the shared flag under the lock, so it is never seen before
+        /// the items sent ahead of it.
         fn set_done(&mut self) {
-            self.1 = true;
+            let _queue = self.0.lock();
+            self.1.store(true, Ordering::Release);
         }
     }
     impl<T> MyReciever<T> for ConcurrentCachingTransiever<T> {
@@ -206,12 +332,53 @@ pub mod caching_async {
         }
 
         fn isdone(&self) -> bool {
-            (match self.0.lock() {
-                Ok(l) => l.is_empty(),
+            match self.0.lock() {
+                Ok(l) => l.is_empty() && self.1.load(Ordering::Acquire),
                 Err(_) => false,
-            } && self.1)
+            }
         }
     }
 }
 
+pub mod batch;
 pub mod caching;
+pub mod dedup;
+pub mod enumerate;
+pub mod inspect;
+pub mod merge;
+pub mod skip;
+pub mod take;
+pub mod throttle;
+pub mod write;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+#[test]
+fn test_concurrent_drain() {
+    use caching_async::ConcurrentCachingTransiever;
+    let shared = ConcurrentCachingTransiever::default();
+    let mut seen = Vec::new();
+    std::thread::scope(|scope| {
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let mut sender = shared.clone();
+                scope.spawn(move || {
+                    for i in 0..1000 {
+                        sender.send(p * 1000 + i);
+                    }
+                })
+            })
+            .collect();
+        while !producers.iter().all(|p| p.is_finished()) {
+            seen.extend(shared.drain());
+        }
+    });
+    let (rest, done) = shared.drain_done();
+    seen.extend(rest);
+    assert!(!done);
+    // Any clone can finish the queue for every handle.
+    shared.clone().set_done();
+    assert_eq!(shared.drain_done(), (Vec::new(), true));
+    seen.sort();
+    assert_eq!(seen, (0..4000).collect::<Vec<_>>());
+    assert!(shared.drain().is_empty());
+}
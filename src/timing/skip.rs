@@ -0,0 +1,66 @@
+use std::marker::PhantomData;
+
+use super::MyReciever;
+
+/// A receiver which throws away the first `n` items of the underlying
+/// receiver. Only items which actually arrive count towards `n`, so a
+/// `receive` which times out leaves the count as it was.
+pub struct SkipReceiver<T, R: MyReciever<T>> {
+    receiver: R,
+    remaining: usize,
+    p: PhantomData<T>,
+}
+
+impl<T, R: MyReciever<T>> SkipReceiver<T, R> {
+    pub fn new(receiver: R, n: usize) -> Self {
+        Self {
+            receiver,
+            remaining: n,
+            p: PhantomData,
+        }
+    }
+}
+impl<T, R: MyReciever<T>> MyReciever<T> for SkipReceiver<T, R> {
+    fn receive(&mut self) -> Option<T> {
+        while self.remaining > 0 {
+            self.receiver.receive()?;
+            self.remaining -= 1;
+        }
+        self.receiver.receive()
+    }
+
+    fn receive_blocking(&mut self) -> Option<T> {
+        while self.remaining > 0 {
+            self.receiver.receive_blocking()?;
+            self.remaining -= 1;
+        }
+        self.receiver.receive_blocking()
+    }
+
+    fn isdone(&self) -> bool {
+        self.receiver.isdone()
+    }
+}
+
+#[test]
+fn test_skip() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut source = CachingTransciever::default();
+    for i in 0..10 {
+        source.send(i);
+    }
+    source.set_done();
+    let rest: Vec<i32> = source.skip(3).collect_vec();
+    assert_eq!(rest, [3, 4, 5, 6, 7, 8, 9]);
+}
+#[test]
+fn test_skip_keeps_count_on_timeout() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut skip = CachingTransciever::default().skip(2);
+    skip.receiver.send(0);
+    assert_eq!(skip.receive(), None);
+    assert_eq!(skip.remaining, 1);
+    skip.receiver.send(1);
+    skip.receiver.send(2);
+    assert_eq!(skip.receive(), Some(2));
+}
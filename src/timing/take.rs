@@ -0,0 +1,55 @@
+use std::marker::PhantomData;
+
+use super::MyReciever;
+
+/// A receiver which is done after yielding `n` items, even if the underlying
+/// receiver has more.
+pub struct TakeReceiver<T, R: MyReciever<T>> {
+    receiver: R,
+    remaining: usize,
+    p: PhantomData<T>,
+}
+
+impl<T, R: MyReciever<T>> TakeReceiver<T, R> {
+    pub fn new(receiver: R, n: usize) -> Self {
+        Self {
+            receiver,
+            remaining: n,
+            p: PhantomData,
+        }
+    }
+}
+impl<T, R: MyReciever<T>> MyReciever<T> for TakeReceiver<T, R> {
+    fn receive(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.receiver.receive()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn receive_blocking(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.receiver.receive_blocking()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn isdone(&self) -> bool {
+        self.remaining == 0 || self.receiver.isdone()
+    }
+}
+
+#[test]
+fn test_take() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut source = CachingTransciever::default();
+    for i in 0..10 {
+        source.send(i);
+    }
+    let taken: Vec<i32> = source.take(3).into_iterator().collect();
+    assert_eq!(taken, [0, 1, 2]);
+}
@@ -11,9 +11,10 @@ impl<T> MySender<T> for ThreadSender<T> {
     fn send(&mut self, value: T) -> bool {
         match &mut self.0 {
             | Some(e) => {
-                if let Err(e) = e.send(value) {
-                    eprintln!("{e}");
-                    panic!();
+                if e.send(value).is_err() {
+                    // The receiver hung up, so nothing more can be delivered.
+                    self.set_done();
+                    return false;
                 }
                 true
             }
@@ -41,11 +42,88 @@ impl<T> MyReciever<T> for ThreadReceiver<T> {
             | Err(RecvTimeoutError::Timeout) => None,
         }
     }
+    fn receive_blocking(&mut self) -> Option<T> {
+        match self.0.recv() {
+            | Ok(a) => Some(a),
+            | Err(_) => {
+                self.1 = true;
+                None
+            }
+        }
+    }
     fn isdone(&self) -> bool {
         self.1
     }
 }
+/// Creates a channel which buffers up to 10 items.
 pub fn channel<T>() -> (ThreadSender<T>, ThreadReceiver<T>) {
-    let (sender, receiver) = std::sync::mpsc::sync_channel(10);
+    channel_with_capacity(10)
+}
+/// Creates a channel which buffers up to `cap` items before the sender
+/// blocks. A capacity of 0 gives a rendezvous channel, where every send waits
+/// for a matching receive.
+pub fn channel_with_capacity<T>(
+    cap: usize,
+) -> (ThreadSender<T>, ThreadReceiver<T>) {
+    let (sender, receiver) = std::sync::mpsc::sync_channel(cap);
     (ThreadSender(Some(sender)), ThreadReceiver(receiver, false))
 }
+#[test]
+fn test_channel_with_capacity() {
+    let (mut sender, receiver) = channel_with_capacity(1000);
+    // Nothing is receiving yet, so this would block without the capacity.
+    for i in 0..1000 {
+        sender.send(i);
+    }
+    sender.set_done();
+    let received: Vec<i32> = receiver.into_iterator().collect();
+    assert_eq!(received, (0..1000).collect::<Vec<_>>());
+}
+#[test]
+fn test_iterator_does_not_poll() {
+    struct Counting(ThreadReceiver<u32>, usize);
+    impl MyReciever<u32> for Counting {
+        fn receive(&mut self) -> Option<u32> {
+            self.1 += 1;
+            self.0.receive()
+        }
+        fn receive_blocking(&mut self) -> Option<u32> {
+            self.1 += 1;
+            self.0.receive_blocking()
+        }
+        fn isdone(&self) -> bool {
+            self.0.isdone()
+        }
+    }
+    let (mut sender, receiver) = channel();
+    let producer = std::thread::spawn(move || {
+        for i in 0..3 {
+            std::thread::sleep(Duration::from_millis(50));
+            sender.send(i);
+        }
+    });
+    let mut counting = Counting(receiver, 0);
+    let received: Vec<u32> = counting.map(|a| a).into_iterator().collect();
+    producer.join().unwrap();
+    assert_eq!(received, [0, 1, 2]);
+    // One call per item plus one to notice the disconnect; polling with a
+    // 10ms timeout would have needed around 15.
+    assert_eq!(counting.1, 4);
+}
+#[test]
+fn test_send_after_receiver_dropped() {
+    let (mut sender, receiver) = channel();
+    drop(receiver);
+    assert!(!sender.send(1));
+    assert!(!sender.send(2));
+}
+#[test]
+fn test_last_item_after_disconnect() {
+    for _ in 0..100 {
+        let (mut sender, receiver) = channel();
+        std::thread::spawn(move || {
+            sender.send(7);
+        });
+        assert_eq!(receiver.collect_vec(), [7]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    marker::PhantomData,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::MyReciever;
+
+/// A receiver which waits so that consecutive items are delivered at least
+/// `interval` apart. The first item is never delayed.
+pub struct ThrottleReceiver<T, R: MyReciever<T>> {
+    receiver: R,
+    interval: Duration,
+    last: Option<Instant>,
+    p: PhantomData<T>,
+}
+
+impl<T, R: MyReciever<T>> ThrottleReceiver<T, R> {
+    pub fn new(receiver: R, interval: Duration) -> Self {
+        Self {
+            receiver,
+            interval,
+            last: None,
+            p: PhantomData,
+        }
+    }
+    /// Sleeps until the interval since the last delivery has passed, then
+    /// delivers the value. Nothing waits when there is no value.
+    fn deliver(&mut self, value: Option<T>) -> Option<T> {
+        let value = value?;
+        if let Some(last) = self.last {
+            thread::sleep(self.interval.saturating_sub(last.elapsed()));
+        }
+        self.last = Some(Instant::now());
+        Some(value)
+    }
+}
+impl<T, R: MyReciever<T>> MyReciever<T> for ThrottleReceiver<T, R> {
+    fn receive(&mut self) -> Option<T> {
+        let value = self.receiver.receive();
+        self.deliver(value)
+    }
+
+    fn receive_blocking(&mut self) -> Option<T> {
+        let value = self.receiver.receive_blocking();
+        self.deliver(value)
+    }
+
+    fn isdone(&self) -> bool {
+        self.receiver.isdone()
+    }
+}
+
+#[test]
+fn test_throttle() {
+    use super::{MySender, caching::CachingTransciever};
+    let mut source = CachingTransciever::default();
+    for i in 0..5 {
+        source.send(i);
+    }
+    source.set_done();
+    let interval = Duration::from_millis(20);
+    let start = Instant::now();
+    let items = source.throttle(interval).collect_vec();
+    assert_eq!(items, [0, 1, 2, 3, 4]);
+    // Sleeping can overshoot but never undershoots.
+    assert!(start.elapsed() >= interval * 4, "{:?}", start.elapsed());
+}
@@ -0,0 +1,100 @@
+//! An async adapter for receivers, available with the `tokio` feature.
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use ::tokio::{sync::mpsc, task};
+use futures::Stream;
+
+use super::MyReciever;
+
+/// A stream of the items of a receiver. The receiver is drained on a blocking
+/// task, so waiting for items never blocks the async executor. The stream
+/// ends once the receiver is done.
+pub struct ReceiverStream<T, R: MyReciever<T>> {
+    items: mpsc::Receiver<T>,
+    p: PhantomData<fn() -> R>,
+}
+
+impl<T: Send + 'static, R: MyReciever<T> + Send + 'static>
+    ReceiverStream<T, R>
+{
+    /// Starts draining the receiver.
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
+    pub fn new(mut receiver: R) -> Self {
+        let (sender, items) = mpsc::channel(16);
+        task::spawn_blocking(move || {
+            // Receiving first means an item which arrived just before the
+            // receiver became done is still sent on.
+            loop {
+                match receiver.receive_blocking() {
+                    | Some(t) => {
+                        if sender.blocking_send(t).is_err() {
+                            break;
+                        }
+                    }
+                    | None if receiver.isdone() => break,
+                    | None => {}
+                }
+            }
+        });
+        Self {
+            items,
+            p: PhantomData,
+        }
+    }
+}
+impl<T, R: MyReciever<T>> Stream for ReceiverStream<T, R> {
+    type Item = T;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.items.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+#[::tokio::test]
+async fn test_receiver_stream() {
+    use futures::StreamExt;
+
+    use super::{MySender, threaded::channel};
+    let (mut sender, receiver) = channel();
+    std::thread::spawn(move || {
+        for i in 0..100 {
+            sender.send(i);
+        }
+        sender.set_done();
+    });
+    let items: Vec<i32> = ReceiverStream::new(receiver).collect().await;
+    assert_eq!(items, (0..100).collect::<Vec<_>>());
+}
+#[cfg(test)]
+#[::tokio::test]
+async fn test_receiver_stream_after_burst() {
+    use std::collections::VecDeque;
+
+    use futures::StreamExt;
+
+    /// A receiver which reports done as soon as the sender does, even while
+    /// the burst it sent is still queued.
+    struct Burst(VecDeque<i32>, bool);
+    impl MyReciever<i32> for Burst {
+        fn receive(&mut self) -> Option<i32> {
+            self.0.pop_front()
+        }
+
+        fn isdone(&self) -> bool {
+            self.1
+        }
+    }
+    let receiver = Burst((0..100).collect(), true);
+    let items: Vec<i32> = ReceiverStream::new(receiver).collect().await;
+    assert_eq!(items, (0..100).collect::<Vec<_>>());
+}
@@ -0,0 +1,146 @@
+use std::{fmt::Display, io::Write};
+
+use super::MySender;
+use crate::base_types::{expressions::Expression, numbers::NumberType};
+
+/// A sender which writes every item as a line to the wrapped writer as soon
+/// as it arrives, so results are not held in memory. The writer is flushed
+/// once the sender is done.
+pub struct WriteSender<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriteSender<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+    /// Gets back the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: Write, T: Display> MySender<T> for WriteSender<W> {
+    /// Writes the item followed by a newline, returning `false` if the
+    /// writer fails.
+    fn send(&mut self, value: T) -> bool {
+        writeln!(self.writer, "{value}").is_ok()
+    }
+
+    fn set_done(&mut self) {
+        // There is nowhere to report a failed flush, and every write which
+        // could have failed already reported it.
+        let _ = self.writer.flush();
+    }
+}
+/// A sender which writes every expression as a row of CSV, under a header of
+/// `value,expression,node_count,depth`. The expression is always quoted, so
+/// its commas and quotes survive.
+pub struct ExpressionCsvWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ExpressionCsvWriter<W> {
+    /// Writes the header to the writer.
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        writeln!(writer, "value,expression,node_count,depth")?;
+        Ok(Self { writer })
+    }
+    /// Gets back the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: Write, T: NumberType> MySender<Expression<T>>
+    for ExpressionCsvWriter<W>
+{
+    /// Writes the expression's row, returning `false` if the writer fails.
+    fn send(&mut self, value: Expression<T>) -> bool {
+        writeln!(
+            self.writer,
+            "{},\"{}\",{},{}",
+            value.get_value(),
+            value.to_string().replace('"', "\"\""),
+            value.node_count(),
+            value.depth()
+        )
+        .is_ok()
+    }
+
+    fn set_done(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+#[test]
+fn test_write_sender() {
+    use crate::base_types::expressions::Operator;
+    let mut sender = WriteSender::new(Vec::new());
+    assert!(sender.send(Expression::Value(3u32)));
+    assert!(sender.send(Expression::Application(
+        5,
+        Operator::Add,
+        Box::new(Expression::Value(2)),
+        Box::new(Expression::Value(3)),
+    )));
+    MySender::<Expression<u32>>::set_done(&mut sender);
+    assert_eq!(
+        String::from_utf8(sender.into_inner()).unwrap(),
+        "3\n2 + 3\n"
+    );
+}
+#[test]
+fn test_write_sender_error() {
+    struct Failing;
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    assert!(!WriteSender::new(Failing).send(1u32));
+}
+#[test]
+fn test_expression_csv_writer() {
+    use crate::base_types::expressions::Operator;
+    /// Splits a row on the commas outside of quotes, unescaping the quotes
+    fn fields(row: &str) -> Vec<String> {
+        let (mut fields, mut field, mut quoted) =
+            (vec![], String::new(), false);
+        let mut chars = row.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                | '"' if quoted && chars.peek() == Some(&'"') => {
+                    field.push(chars.next().unwrap())
+                }
+                | '"' => quoted = !quoted,
+                | ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                | c => field.push(c),
+            }
+        }
+        fields.push(field);
+        fields
+    }
+    let value = |t| Box::new(Expression::Value(t));
+    let sum = Expression::Application(5u32, Operator::Add, value(2), value(3));
+    let product = Expression::Application(
+        20,
+        Operator::Mul,
+        Box::new(sum.clone()),
+        value(4),
+    );
+    let mut writer = ExpressionCsvWriter::new(Vec::new()).unwrap();
+    for expr in [Expression::Value(7), sum, product] {
+        assert!(writer.send(expr));
+    }
+    MySender::<Expression<u32>>::set_done(&mut writer);
+    let csv = String::from_utf8(writer.into_inner()).unwrap();
+    let rows: Vec<Vec<String>> = csv.lines().map(fields).collect();
+    assert_eq!(rows[0], ["value", "expression", "node_count", "depth"]);
+    assert_eq!(rows[1], ["7", "7", "1", "1"]);
+    assert_eq!(rows[2], ["5", "2 + 3", "3", "2"]);
+    assert_eq!(rows[3], ["20", "(2 + 3) * 4", "5", "3"]);
+    assert!(rows.iter().all(|row| row.len() == 4));
+    assert_eq!(fields(r#"1,"a ""b"", c",2,3"#)[1], r#"a "b", c"#);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "parsing")]
+use std::process::Command;
+
+#[test]
+fn test_solves_from_args() {
+    let output = Command::new(env!("CARGO_BIN_EXE_countdown"))
+        .args(["--numbers", "2,3,7", "--target", "13"])
+        .args(["--modulus", "0", "--ops", "+-*/"])
+        .output()
+        .expect("Could not run the binary");
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.lines().any(|line| line.starts_with("13\t")),
+        "{stderr}"
+    );
+}
+#[test]
+fn test_rejects_unknown_flags() {
+    let output = Command::new(env!("CARGO_BIN_EXE_countdown"))
+        .arg("--verbose")
+        .output()
+        .expect("Could not run the binary");
+    assert!(!output.status.success());
+}
+#[test]
+fn test_json_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_countdown"))
+        .args(["--numbers", "2,3,4,5", "--target", "9", "--modulus", "0"])
+        .args(["--ops", "+-*/", "--format", "json"])
+        .output()
+        .expect("Could not run the binary");
+    assert!(output.status.success());
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let entries = json.as_array().unwrap();
+    // The summary on stderr reports how many expressions there should be.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let count: usize = stderr
+        .lines()
+        .find_map(|line| line.split_once(" expressions found"))
+        .and_then(|(count, _)| count.parse().ok())
+        .unwrap();
+    assert!(count > 1);
+    assert_eq!(entries.len(), count);
+    for entry in entries {
+        assert_eq!(entry["value"], 9);
+        assert!(entry["expression"].is_string());
+        assert!(entry["node_count"].as_u64().unwrap() >= 3);
+    }
+}
+#[test]
+fn test_rejects_target_outside_modulus() {
+    let output = Command::new(env!("CARGO_BIN_EXE_countdown"))
+        .args(["--numbers", "2,3", "--target", "9", "--modulus", "7"])
+        .args(["--ops", "+-*/"])
+        .output()
+        .expect("Could not run the binary");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Target 9 can never be reached"), "{stderr}");
+}